@@ -0,0 +1,40 @@
+use criterion::{black_box, criterion_group, criterion_main, BenchmarkId, Criterion, Throughput};
+
+use compiler::Lexer;
+
+// Covers every lexer state: numbers in all bases, plain, raw and interpolated strings, characters with escapes, operators, symbols, keywords, scopes and (doc) comments
+const SNIPPET: &str = r###"let total == (0x1F_FF + 0b1010 * 6.02e23) / 1_000 ^ 2 - 0o777; // trailing comment
+/// doc comment
+/* block /* nested */ comment */ "string with \"escapes\" and \u{1F600}" != naïve_symbol && !false;
+"interpolated ${total + 0x10} string" + r##"raw "string" \path"##;
+{ value_1, value_2 } >= 42.5e-3 || identifier <= -12 ** 3 || 'x' != '\u{263A}';
+"###;
+
+fn input_of_size(size: usize) -> String {
+    SNIPPET.repeat(size / SNIPPET.len() + 1)
+}
+
+fn lexer_throughput(c: &mut Criterion) {
+    let mut group = c.benchmark_group("lexer");
+
+    for size in [1 << 10, 1 << 20, 8 << 20] {
+        let input = input_of_size(size);
+        group.throughput(Throughput::Bytes(input.len() as u64));
+        group.sample_size(if size > 1 << 20 { 10 } else { 50 });
+
+        group.bench_with_input(BenchmarkId::new("parse", size), &input, |b, input| {
+            b.iter(|| Lexer::new(black_box(input)).parse().unwrap())
+        });
+        group.bench_with_input(BenchmarkId::new("stream", size), &input, |b, input| {
+            b.iter(|| Lexer::new(black_box(input)).filter(Result::is_ok).count())
+        });
+        group.bench_with_input(BenchmarkId::new("lossless", size), &input, |b, input| {
+            b.iter(|| Lexer::new(black_box(input)).lossless().parse().unwrap())
+        });
+    }
+
+    group.finish();
+}
+
+criterion_group!(benches, lexer_throughput);
+criterion_main!(benches);
@@ -1,95 +1,224 @@
-use anyhow::{anyhow, Result};
+use crate::error::{CompilerError, CompilerErrorKind};
+use crate::solver::{builtins, Expression, Instruction};
+use crate::lexer::{Operator, Token, TokenKind, TokenQueue};
+use crate::parser::{Expr, Parser, Stmt};
 
-use crate::solver::Expression;
-use crate::lexer::{Token, TokenKind, TokenQueue};
+/// Generates the instructions the solver runs from the syntax tree `Parser` builds.
+pub struct Compiler;
 
-pub struct Compiler {
-    operator_stack: Vec<Token>,
-    previous_token: Option<Token>,
+impl Default for Compiler {
+    fn default() -> Self {
+        Self::new()
+    }
 }
 
 impl Compiler {
     pub fn new() -> Self {
-        Self {
-            operator_stack: vec![],
-            previous_token: None
+        Self
+    }
+
+    /// Parses `input` and lowers the program it holds, as `Parser` then `lower` would.
+    pub fn to_expression(&self, input: &TokenQueue) -> Result<Expression, CompilerError> {
+        let statements = Parser::new(input).parse()?;
+        self.lower(&statements)
+    }
+
+    /// Generates the instructions of a program.
+    ///
+    /// Its value is the one of its last statement, the top level not being a scope of its own.
+    pub fn lower(&self, statements: &[Stmt]) -> Result<Expression, CompilerError> {
+        let mut rpn = vec![];
+        Self::lower_statements(statements, &mut rpn)?;
+        Ok(Expression::new(rpn))
+    }
+
+    /// Call of a built-in function, checked to exist and to accept `arity` arguments.
+    fn call(function: Token, arity: usize) -> Result<Instruction, CompilerError> {
+        match builtins::function(&function.as_string()) {
+            None => Err(CompilerError::new(CompilerErrorKind::UnknownFunction, &function.as_string(), function.span())),
+            Some(builtin) if !builtin.arity().accepts(arity) => {
+                Err(CompilerError::new(CompilerErrorKind::ArgumentCountMismatch, &function.as_string(), function.span()))
+            }
+            Some(_) => Ok(Instruction::Call { function, arity }),
         }
     }
 
-    pub fn to_expression(mut self, input: &TokenQueue) -> Result<Expression> {
-        let mut rpn: TokenQueue = TokenQueue::new();
+    /// Applies the operator of a compound assignment, its left operand already loaded, then stores the result.
+    fn store(token: Token, operator: Operator, target: Token, rpn: &mut Vec<Instruction>) {
+        if let Some(compound) = operator.compound() {
+            let mut compound_token = token;
+            compound_token.update_kind(TokenKind::Operator(compound));
+            rpn.push(Instruction::Token(compound_token));
+        }
+        rpn.push(Instruction::Store(target));
+    }
 
-        for token in input.iter() {
-            match token.kind() {
-                TokenKind::NumericLiteral => {
-                    self.previous_token = Some(token.clone());
-                    rpn.push_back(token.clone());
-                }
-                TokenKind::Operator(mut o1) => {
-                    o1 = o1.correct_arity(&self.previous_token)?;
-
-                    while let Some(o2) = self.operator_stack.last() {
-                        match o2.kind() {
-                            TokenKind::Operator(o2) => {
-                                if o2.precedence() >= o1.precedence() {
-                                    rpn.push_back(self.operator_stack.pop().unwrap());
-                                } else {
-                                    break;
-                                }
-                            }
-                            TokenKind::OpeningParenthesis => {
-                                break;
-                            }
-                            _ => {}
-                        }
-                    }
-                    let mut updated_token = token.clone();
-                    updated_token.update_kind(TokenKind::Operator(o1));
-
-                    self.previous_token = Some(updated_token.clone());
-                    self.operator_stack.push(updated_token);
+    /// Every statement leaves its value on the stack, only the last one keeps it.
+    fn lower_statements(statements: &[Stmt], rpn: &mut Vec<Instruction>) -> Result<(), CompilerError> {
+        for (index, statement) in statements.iter().enumerate() {
+            if index > 0 {
+                rpn.push(Instruction::Pop);
+            }
+
+            match statement {
+                Stmt::Expression(expression) => Self::lower_expression(expression, rpn)?,
+                Stmt::Let { name, value, .. } => {
+                    Self::lower_expression(value, rpn)?;
+                    rpn.push(Instruction::Define(name.clone()));
                 }
-                TokenKind::OpeningParenthesis => {
-                    self.operator_stack.push(token.clone());
-                    self.previous_token = Some(token.clone());
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Appends the instructions of `expression` to `rpn`, operands first and operators after them.
+    fn lower_expression(expression: &Expr, rpn: &mut Vec<Instruction>) -> Result<(), CompilerError> {
+        match expression {
+            Expr::Literal { token, .. } | Expr::Variable(token) => rpn.push(Instruction::Token(token.clone())),
+            Expr::Interpolation { parts, token } => {
+                for part in parts {
+                    Self::lower_expression(part, rpn)?;
                 }
-                TokenKind::ClosingParenthesis => {
-                    while let Some(last) = self.operator_stack.last() {
-                        match last.kind() {
-                            TokenKind::Operator(_) => {
-                                rpn.push_back(self.operator_stack.pop().unwrap());
-                            }
-                            TokenKind::OpeningParenthesis => {
-                                self.operator_stack.pop();
-                                break;
-                            }
-                            TokenKind::Unknown => {
-                                return Err(anyhow!("Somehow we missed a parsing error here"));
-                            }
-                            _ => {}
-                        }
-                    }
-                    self.previous_token = Some(token.clone());
+                rpn.push(Instruction::Concat { count: parts.len(), token: token.clone() });
+            }
+            Expr::Unary { operator, operand } => {
+                Self::lower_expression(operand, rpn)?;
+                rpn.push(Instruction::Token(operator.clone()));
+            }
+            Expr::Binary { operator, left, right } => {
+                Self::lower_expression(left, rpn)?;
+                Self::lower_expression(right, rpn)?;
+                rpn.push(Instruction::Token(operator.clone()));
+            }
+            Expr::Assign { operator, target, value } => {
+                let TokenKind::Operator(assignment) = operator.kind() else { unreachable!("assignments are operators") };
+                if assignment.compound().is_some() {
+                    rpn.push(Instruction::Token(target.clone()));
                 }
-                TokenKind::OpeningScope |
-                TokenKind::ClosingScope |
-                TokenKind::Symbol |
-                TokenKind::Separator |
-                TokenKind::StringLiteral |
-                TokenKind::EndOfStatement |
-                TokenKind::Keyword => {
-                    return Err(anyhow!("This is not handled yet!"));
+                Self::lower_expression(value, rpn)?;
+                Self::store(operator.clone(), assignment, target.clone(), rpn);
+            }
+            Expr::Conditional { operator, condition, then, otherwise } => {
+                Self::lower_expression(condition, rpn)?;
+                let condition_jump = rpn.len();
+                rpn.push(Instruction::JumpUnless { target: 0, token: operator.clone() });
+                Self::lower_expression(then, rpn)?;
+                let then_jump = rpn.len();
+                rpn.push(Instruction::Jump { target: 0 });
+
+                let alternative = rpn.len();
+                if let Instruction::JumpUnless { target, .. } = &mut rpn[condition_jump] {
+                    *target = alternative;
                 }
-                TokenKind::Unknown => {
-                    return Err(anyhow!("Somehow we missed a parsing error here"));
+                Self::lower_expression(otherwise, rpn)?;
+                rpn[then_jump] = Instruction::Jump { target: rpn.len() };
+            }
+            Expr::Call { callee, arguments } => {
+                for argument in arguments {
+                    Self::lower_expression(argument, rpn)?;
                 }
+                let mut function = callee.clone();
+                function.update_kind(TokenKind::Function);
+                rpn.push(Self::call(function, arguments.len())?);
+            }
+            Expr::Block { statements, .. } => {
+                rpn.push(Instruction::EnterScope);
+                Self::lower_statements(statements, rpn)?;
+                rpn.push(Instruction::LeaveScope);
             }
         }
 
-        while let Some(op) = self.operator_stack.pop() {
-            rpn.push_back(op);
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::error::RuntimeErrorKind;
+    use crate::lexer::{Lexer, Number};
+    use crate::solver::Value;
+
+    fn compile(input: &str) -> Result<Expression, CompilerError> {
+        Compiler::new().to_expression(&Lexer::new(input).parse().unwrap())
+    }
+
+    fn solve(input: &str) -> Value {
+        compile(input).unwrap().solve().unwrap()
+    }
+
+    #[test]
+    fn conditional_jumps_over_the_branch_not_taken() {
+        let tokens = Lexer::new("0 ? 1 : 2").parse().unwrap();
+        let expression = Compiler::new().to_expression(&tokens).unwrap();
+        assert_eq!(expression.instructions(), [
+            Instruction::Token(tokens[0].clone()),
+            Instruction::JumpUnless { target: 4, token: tokens[1].clone() },
+            Instruction::Token(tokens[2].clone()),
+            Instruction::Jump { target: 5 },
+            Instruction::Token(tokens[4].clone()),
+        ]);
+    }
+
+    #[test]
+    fn statements_and_blocks() {
+        let tokens = Lexer::new("let x = 1; { x }").parse().unwrap();
+        let expression = Compiler::new().to_expression(&tokens).unwrap();
+        assert_eq!(expression.instructions(), [
+            Instruction::Token(tokens[3].clone()),
+            Instruction::Define(tokens[1].clone()),
+            Instruction::Pop,
+            Instruction::EnterScope,
+            Instruction::Token(tokens[6].clone()),
+            Instruction::LeaveScope,
+        ]);
+    }
+
+    #[test]
+    fn call_carries_its_argument_count() {
+        let tokens = Lexer::new("max(1, 2)").parse().unwrap();
+        let expression = Compiler::new().to_expression(&tokens).unwrap();
+        let mut function = tokens[0].clone();
+        function.update_kind(TokenKind::Function);
+        assert_eq!(expression.instructions(), [
+            Instruction::Token(tokens[2].clone()),
+            Instruction::Token(tokens[4].clone()),
+            Instruction::Call { function, arity: 2 },
+        ]);
+        assert_eq!(expression.solve().unwrap(), Value::Number(Number::Integer(2)));
+    }
+
+    #[test]
+    fn calls_are_checked_against_the_builtins() {
+        assert_eq!(compile("nope(1)").unwrap_err().kind, CompilerErrorKind::UnknownFunction);
+        assert_eq!(compile("math.nope(1)").unwrap_err().kind, CompilerErrorKind::UnknownFunction);
+        for input in ["sqrt()", "sqrt(1, 2)", "log(8)", "min()", "clamp(1, 2)"] {
+            assert_eq!(compile(input).unwrap_err().kind, CompilerErrorKind::ArgumentCountMismatch, "{input}");
         }
+    }
 
-        Ok(Expression::new(rpn))
+    #[test]
+    fn inexact_division_is_a_float() {
+        assert_eq!(solve("1/2"), Value::Number(Number::Float(0.5)));
+        assert_eq!(solve("4/2"), Value::Number(Number::Integer(2)));
+    }
+
+    #[test]
+    fn exponent_is_right_associative() {
+        assert_eq!(solve("2^3^2"), Value::Number(Number::Integer(512)));
+    }
+
+    #[test]
+    fn conditional_only_runs_its_branch() {
+        assert_eq!(solve("0 ? 1/0 : 3"), Value::Number(Number::Integer(3)));
+        assert_eq!(compile("1 ? 1/0 : 3").unwrap().solve().unwrap_err().kind, RuntimeErrorKind::DivisionByZero);
     }
-}
\ No newline at end of file
+
+    #[test]
+    fn block_shadows_until_it_ends() {
+        assert_eq!(solve("let x = 1; { let x = 2; x }"), Value::Number(Number::Integer(2)));
+        assert_eq!(solve("let x = 1; { let x = 2; x }; x"), Value::Number(Number::Integer(1)));
+        assert_eq!(solve("let x = 1; { x = 2 }; x"), Value::Number(Number::Integer(2)));
+    }
+}
@@ -0,0 +1,350 @@
+use std::fmt::{Display, Formatter};
+
+use crate::lexer::Span;
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum Error {
+    Lexer(LexerError),
+    Compiler(CompilerError),
+    Runtime(RuntimeError),
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct LexerError {
+    pub kind: LexerErrorKind,
+    pub token: String,
+    pub span: Span,
+}
+
+#[derive(Debug, Copy, Clone, Eq, PartialEq)]
+pub enum LexerErrorKind {
+    EmptyInput,
+    MultipleDecimalSeparators,
+    InvalidNumber,
+    BadNumericLiteral,
+    InvalidBinaryNumber,
+    InvalidHexadecimalNumber,
+    UnterminatedString,
+    UnrecognizedOperator,
+    DanglingOperator,
+    TrailingSlash,
+    UnterminatedComment,
+    UnmatchedClosingParenthesis,
+    UnclosedParenthesis,
+    UnmatchedClosingScope,
+    UnclosedScope,
+    UnexpectedCharacter,
+    UnknownEscape,
+    MalformedEscape,
+    InvalidOctalNumber,
+    MissingDigits,
+    NumericOverflow,
+    MisplacedDigitSeparator,
+    MalformedExponent,
+    UnterminatedCharacter,
+    InvalidCharacterLiteral,
+    UnterminatedInterpolation,
+    EmptyInterpolation,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct CompilerError {
+    pub kind: CompilerErrorKind,
+    pub token: String,
+    pub span: Span,
+}
+
+#[derive(Debug, Copy, Clone, Eq, PartialEq)]
+pub enum CompilerErrorKind {
+    UnsupportedToken,
+    UnknownToken,
+    MissingConditionalElse,
+    UnmatchedConditionalElse,
+    InvalidAssignmentTarget,
+    InvalidMemberAccess,
+    ExpectedOperand,
+    UnexpectedToken,
+    UnknownFunction,
+    ArgumentCountMismatch,
+    InvalidDeclaration,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct RuntimeError {
+    pub kind: RuntimeErrorKind,
+    pub token: String,
+    pub span: Span,
+}
+
+#[derive(Debug, Copy, Clone, Eq, PartialEq)]
+pub enum RuntimeErrorKind {
+    MissingOperand,
+    LeftoverOperands,
+    EmptyExpression,
+    DivisionByZero,
+    IntegerOverflow,
+    InvalidOperandType,
+    NonIntegerOperand,
+    InvalidShiftAmount,
+    UndefinedVariable,
+    UnknownFunction,
+}
+
+impl Error {
+    pub fn span(&self) -> Span {
+        match self {
+            Error::Lexer(err) => err.span,
+            Error::Compiler(err) => err.span,
+            Error::Runtime(err) => err.span,
+        }
+    }
+}
+
+impl LexerError {
+    pub fn new(kind: LexerErrorKind, token: &str, span: Span) -> Self {
+        Self {
+            kind,
+            token: token.to_string(),
+            span,
+        }
+    }
+
+    pub fn code(&self) -> &'static str {
+        self.kind.code()
+    }
+}
+
+impl CompilerError {
+    pub fn new(kind: CompilerErrorKind, token: &str, span: Span) -> Self {
+        Self {
+            kind,
+            token: token.to_string(),
+            span,
+        }
+    }
+
+    pub fn code(&self) -> &'static str {
+        self.kind.code()
+    }
+}
+
+impl RuntimeError {
+    pub fn new(kind: RuntimeErrorKind, token: &str, span: Span) -> Self {
+        Self {
+            kind,
+            token: token.to_string(),
+            span,
+        }
+    }
+
+    pub fn code(&self) -> &'static str {
+        self.kind.code()
+    }
+}
+
+// Error codes are part of the public contract: never renumber, only append.
+impl LexerErrorKind {
+    pub fn code(&self) -> &'static str {
+        match self {
+            LexerErrorKind::EmptyInput => "L0001",
+            LexerErrorKind::MultipleDecimalSeparators => "L0002",
+            LexerErrorKind::InvalidNumber => "L0003",
+            LexerErrorKind::BadNumericLiteral => "L0004",
+            LexerErrorKind::InvalidBinaryNumber => "L0005",
+            LexerErrorKind::InvalidHexadecimalNumber => "L0006",
+            LexerErrorKind::UnterminatedString => "L0007",
+            LexerErrorKind::UnrecognizedOperator => "L0008",
+            LexerErrorKind::DanglingOperator => "L0009",
+            LexerErrorKind::TrailingSlash => "L0010",
+            LexerErrorKind::UnterminatedComment => "L0011",
+            LexerErrorKind::UnmatchedClosingParenthesis => "L0012",
+            LexerErrorKind::UnclosedParenthesis => "L0013",
+            LexerErrorKind::UnmatchedClosingScope => "L0014",
+            LexerErrorKind::UnclosedScope => "L0015",
+            LexerErrorKind::UnexpectedCharacter => "L0016",
+            LexerErrorKind::UnknownEscape => "L0017",
+            LexerErrorKind::MalformedEscape => "L0018",
+            LexerErrorKind::InvalidOctalNumber => "L0019",
+            LexerErrorKind::MissingDigits => "L0020",
+            LexerErrorKind::NumericOverflow => "L0021",
+            LexerErrorKind::MisplacedDigitSeparator => "L0022",
+            LexerErrorKind::MalformedExponent => "L0023",
+            LexerErrorKind::UnterminatedCharacter => "L0024",
+            LexerErrorKind::InvalidCharacterLiteral => "L0025",
+            LexerErrorKind::UnterminatedInterpolation => "L0026",
+            LexerErrorKind::EmptyInterpolation => "L0027",
+        }
+    }
+
+    fn message(&self) -> &'static str {
+        match self {
+            LexerErrorKind::EmptyInput => "No input provided",
+            LexerErrorKind::MultipleDecimalSeparators => "Multiple decimal separator found in the same numeric token",
+            LexerErrorKind::InvalidNumber => "Invalid number/symbol",
+            LexerErrorKind::BadNumericLiteral => "Bad numeric literal",
+            LexerErrorKind::InvalidBinaryNumber => "Invalid binary number",
+            LexerErrorKind::InvalidHexadecimalNumber => "Invalid hexadecimal number",
+            LexerErrorKind::UnterminatedString => "Missing quotation mark \" for string",
+            LexerErrorKind::UnrecognizedOperator => "Unrecognized operator",
+            LexerErrorKind::DanglingOperator => "Operators should always be followed by another token",
+            LexerErrorKind::TrailingSlash => "Trailing slash",
+            LexerErrorKind::UnterminatedComment => "Unfinished multiline comment block",
+            LexerErrorKind::UnmatchedClosingParenthesis => "Closing parenthesis without a matching opening one",
+            LexerErrorKind::UnclosedParenthesis => "Parentheses are not balanced, unclosed parenthesis",
+            LexerErrorKind::UnmatchedClosingScope => "Closing scope bracket without a matching opening one",
+            LexerErrorKind::UnclosedScope => "Scope brackets are not balanced, unclosed scope",
+            LexerErrorKind::UnexpectedCharacter => "Unexpected character",
+            LexerErrorKind::UnknownEscape => "Unknown escape sequence",
+            LexerErrorKind::MalformedEscape => "Malformed escape sequence",
+            LexerErrorKind::InvalidOctalNumber => "Invalid octal number",
+            LexerErrorKind::MissingDigits => "Missing digits after the numeric prefix",
+            LexerErrorKind::NumericOverflow => "Numeric literal is too large",
+            LexerErrorKind::MisplacedDigitSeparator => "Digit separator `_` must follow a digit",
+            LexerErrorKind::MalformedExponent => "Malformed exponent",
+            LexerErrorKind::UnterminatedCharacter => "Missing quotation mark ' for character",
+            LexerErrorKind::InvalidCharacterLiteral => "Character literals must hold exactly one character",
+            LexerErrorKind::UnterminatedInterpolation => "Missing closing bracket } for string interpolation",
+            LexerErrorKind::EmptyInterpolation => "String interpolation without an expression",
+        }
+    }
+}
+
+impl CompilerErrorKind {
+    pub fn code(&self) -> &'static str {
+        match self {
+            CompilerErrorKind::UnsupportedToken => "C0001",
+            CompilerErrorKind::UnknownToken => "C0002",
+            CompilerErrorKind::MissingConditionalElse => "C0003",
+            CompilerErrorKind::UnmatchedConditionalElse => "C0004",
+            CompilerErrorKind::InvalidAssignmentTarget => "C0005",
+            CompilerErrorKind::InvalidMemberAccess => "C0006",
+            CompilerErrorKind::ExpectedOperand => "C0007",
+            CompilerErrorKind::UnexpectedToken => "C0008",
+            CompilerErrorKind::UnknownFunction => "C0009",
+            CompilerErrorKind::ArgumentCountMismatch => "C0010",
+            CompilerErrorKind::InvalidDeclaration => "C0011",
+        }
+    }
+
+    fn message(&self) -> &'static str {
+        match self {
+            CompilerErrorKind::UnsupportedToken => "This is not handled yet",
+            CompilerErrorKind::UnknownToken => "Somehow we missed a parsing error here",
+            CompilerErrorKind::MissingConditionalElse => "Conditional without its `:` alternative",
+            CompilerErrorKind::UnmatchedConditionalElse => "Alternative without a matching conditional `?`",
+            CompilerErrorKind::InvalidAssignmentTarget => "Only a variable can be assigned to, not a literal or an expression",
+            CompilerErrorKind::InvalidMemberAccess => "Member access needs a name on both sides of `.`",
+            CompilerErrorKind::ExpectedOperand => "Expected an operand",
+            CompilerErrorKind::UnexpectedToken => "Unexpected token",
+            CompilerErrorKind::UnknownFunction => "Unknown function",
+            CompilerErrorKind::ArgumentCountMismatch => "Wrong number of arguments for this function",
+            CompilerErrorKind::InvalidDeclaration => "Expected `let name = value`",
+        }
+    }
+}
+
+impl RuntimeErrorKind {
+    pub fn code(&self) -> &'static str {
+        match self {
+            RuntimeErrorKind::MissingOperand => "R0001",
+            RuntimeErrorKind::LeftoverOperands => "R0002",
+            RuntimeErrorKind::EmptyExpression => "R0003",
+            RuntimeErrorKind::DivisionByZero => "R0004",
+            RuntimeErrorKind::IntegerOverflow => "R0005",
+            RuntimeErrorKind::InvalidOperandType => "R0006",
+            RuntimeErrorKind::NonIntegerOperand => "R0007",
+            RuntimeErrorKind::InvalidShiftAmount => "R0008",
+            RuntimeErrorKind::UndefinedVariable => "R0009",
+            RuntimeErrorKind::UnknownFunction => "R0010",
+        }
+    }
+
+    fn message(&self) -> &'static str {
+        match self {
+            RuntimeErrorKind::MissingOperand => "Malformed Expression, missing operand",
+            RuntimeErrorKind::LeftoverOperands => "Malformed Expression, leftover operands",
+            RuntimeErrorKind::EmptyExpression => "Empty Expression",
+            RuntimeErrorKind::DivisionByZero => "Integer division by zero",
+            RuntimeErrorKind::IntegerOverflow => "Integer overflow",
+            RuntimeErrorKind::InvalidOperandType => "Operator does not apply to this type of operand",
+            RuntimeErrorKind::NonIntegerOperand => "Operator only applies to integers",
+            RuntimeErrorKind::InvalidShiftAmount => "Shift amount must be between 0 and 63",
+            RuntimeErrorKind::UndefinedVariable => "Undefined variable",
+            RuntimeErrorKind::UnknownFunction => "Unknown function",
+        }
+    }
+}
+
+
+// Display Implementations
+fn write_error(f: &mut Formatter<'_>, stage: &str, code: &str, message: &str, token: &str, span: &Span) -> std::fmt::Result {
+    if token.is_empty() {
+        write!(f, "[{stage} {code}] {message} at {span}")
+    } else {
+        write!(f, "[{stage} {code}] {message} `{token}` at {span}")
+    }
+}
+
+impl Display for LexerError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        write_error(f, "LEXER", self.code(), self.kind.message(), &self.token, &self.span)
+    }
+}
+
+impl Display for CompilerError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        write_error(f, "COMPILER", self.code(), self.kind.message(), &self.token, &self.span)
+    }
+}
+
+impl Display for RuntimeError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        write_error(f, "RUNTIME", self.code(), self.kind.message(), &self.token, &self.span)
+    }
+}
+
+impl Display for Error {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Error::Lexer(err) => write!(f, "{err}"),
+            Error::Compiler(err) => write!(f, "{err}"),
+            Error::Runtime(err) => write!(f, "{err}"),
+        }
+    }
+}
+
+
+// Error Implementations
+impl std::error::Error for LexerError {}
+
+impl std::error::Error for CompilerError {}
+
+impl std::error::Error for RuntimeError {}
+
+impl std::error::Error for Error {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            Error::Lexer(err) => Some(err),
+            Error::Compiler(err) => Some(err),
+            Error::Runtime(err) => Some(err),
+        }
+    }
+}
+
+impl From<LexerError> for Error {
+    fn from(err: LexerError) -> Self {
+        Error::Lexer(err)
+    }
+}
+
+impl From<CompilerError> for Error {
+    fn from(err: CompilerError) -> Self {
+        Error::Compiler(err)
+    }
+}
+
+impl From<RuntimeError> for Error {
+    fn from(err: RuntimeError) -> Self {
+        Error::Runtime(err)
+    }
+}
@@ -1,40 +1,91 @@
 use std::collections::VecDeque;
 
-use anyhow::Result;
-
-pub use shared_types::{Token, TokenKind};
-use shared_types::states::{StartState, State, TemporaryData};
+pub use shared_types::{Number, Span, StringPart, Token, TokenKind, TokenValue, Trivia, TriviaKind};
+pub use shared_types::keywords::KeywordKind;
+pub use shared_types::operators::{Associativity, Operator};
+use crate::error::LexerError;
+use shared_types::states::{State, TemporaryData};
 
 mod shared_types;
 
-pub struct Lexer {
-    input: String,
+/// Pull-based lexer, tokens are produced one at a time as the iterator is advanced.
+///
+/// By default the first error ends the stream. In recovering mode every diagnostic is yielded as an `Err`
+/// and lexing carries on, invalid lexemes being yielded as `TokenKind::Unknown` tokens.
+pub struct Lexer<'a> {
+    temporary_data: TemporaryData<'a>,
+    state: Option<State>,
+    recover: bool,
 }
 
 pub type TokenQueue = VecDeque<Token>;
 
-impl Lexer {
-    pub fn new(input: String) -> Self {
+impl<'a> Lexer<'a> {
+    pub fn new(input: &'a str) -> Self {
         Self {
-            input,
+            temporary_data: TemporaryData::new(input),
+            state: Some(State::Start),
+            recover: false,
         }
     }
 
-    pub fn parse(self) -> Result<TokenQueue> {
-        let cloned = self.input.clone();
-        let iter = cloned.chars().peekable();
-        let mut tmp = TemporaryData::new(self.input.clone(), iter);
-        let mut state: Box<dyn State> = Box::new(StartState);
+    pub fn recovering(mut self) -> Self {
+        self.recover = true;
+        self
+    }
 
-        loop {
-            (state, tmp) = state.handle(tmp)?;
-            if state.is_final() {
-                (_, tmp) = state.handle(tmp)?;
-                break;
+    /// Every token keeps its exact source text and the whitespace and comments around it as trivia,
+    /// so [`to_source`] gives back the input byte for byte. The last token is a `TokenKind::EndOfInput`.
+    pub fn lossless(mut self) -> Self {
+        self.temporary_data.keep_trivia();
+        self
+    }
+
+    pub fn parse(self) -> Result<TokenQueue, LexerError> {
+        self.collect()
+    }
+
+    /// Lexes the whole input even when it contains mistakes.
+    ///
+    /// Invalid lexemes are kept in the queue as `TokenKind::Unknown` tokens carrying their diagnostic,
+    /// and lexing resumes at the next token boundary so every mistake is reported in a single pass.
+    pub fn parse_recovering(self) -> (TokenQueue, Vec<LexerError>) {
+        let mut output = TokenQueue::new();
+        let mut diagnostics = vec![];
+
+        for item in self.recovering() {
+            match item {
+                Ok(token) => output.push_back(token),
+                Err(err) => diagnostics.push(err),
             }
         }
 
-        Ok(tmp.output())
+        (output, diagnostics)
+    }
+}
+
+impl Iterator for Lexer<'_> {
+    type Item = Result<Token, LexerError>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        loop {
+            if let Some(token) = self.temporary_data.next_token() {
+                return Some(Ok(token));
+            }
+
+            let state = self.state.take()?;
+            let final_state = state.is_final();
+            match state.handle(&mut self.temporary_data) {
+                Ok(_) if final_state => {}
+                Ok(next) => self.state = Some(next),
+                Err(err) => {
+                    if self.recover {
+                        self.state = Some(self.temporary_data.recover(&err, state));
+                    }
+                    return Some(Err(err));
+                }
+            }
+        }
     }
 }
 
@@ -52,4 +103,144 @@ pub fn display_queue(queue: &TokenQueue) -> String {
             acc
         },
     )
-}
\ No newline at end of file
+}
+
+/// Source text of tokens produced by a lossless lexer, trivia included.
+pub fn to_source(queue: &TokenQueue) -> String {
+    let mut source = String::new();
+    for token in queue {
+        token.leading_trivia().iter().for_each(|trivia| source.push_str(trivia.text()));
+        source.push_str(token.source().unwrap_or_default());
+        token.trailing_trivia().iter().for_each(|trivia| source.push_str(trivia.text()));
+    }
+    source
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::error::LexerErrorKind;
+
+    fn first_value(input: &str) -> Option<TokenValue> {
+        Lexer::new(input).parse().unwrap().front().and_then(Token::value).cloned()
+    }
+
+    #[test]
+    fn lossless_tokens_give_back_the_source() {
+        let inputs = [
+            "",
+            "  \t\r\n",
+            "let x = 0x1F_FF + 6.02e23; // trailing\r\n/// doc\n{ x }  ",
+            "\"a\\n${1 + 2}b\" r#\"raw \"quoted\"\"# 'c' π\u{a0}/* nested /* block */ */",
+            "1 $ 2 @ (3",
+        ];
+
+        for input in inputs {
+            let (tokens, _) = Lexer::new(input).lossless().parse_recovering();
+            assert_eq!(to_source(&tokens), input);
+            assert_eq!(tokens.back().map(Token::kind), Some(TokenKind::EndOfInput), "{input:?}");
+        }
+    }
+
+    #[test]
+    fn recovering_reports_every_mistake() {
+        let (tokens, diagnostics) = Lexer::new("1 $ 2 @ 3").parse_recovering();
+        let kinds: Vec<_> = diagnostics.iter().map(|err| err.kind).collect();
+        assert_eq!(kinds, [LexerErrorKind::UnrecognizedOperator, LexerErrorKind::UnrecognizedOperator]);
+        assert_eq!(tokens.iter().filter(|token| token.kind() == TokenKind::Unknown).count(), 2);
+        assert_eq!(tokens.len(), 5);
+    }
+
+    #[test]
+    fn comment_at_end_of_input_is_not_an_unknown_token() {
+        let (tokens, diagnostics) = Lexer::new("(1 // c").parse_recovering();
+        assert_eq!(tokens.len(), 2);
+        assert_eq!(diagnostics.len(), 1);
+        assert_eq!(diagnostics[0].kind, LexerErrorKind::UnclosedParenthesis);
+
+        let (tokens, _) = Lexer::new("(1 // c").lossless().parse_recovering();
+        assert_eq!(to_source(&tokens), "(1 // c");
+    }
+
+    #[test]
+    fn unicode_names_and_whitespace() {
+        let tokens = Lexer::new("π\u{a0}+\u{2003}été\u{a0}").parse().unwrap();
+        let names: Vec<_> = tokens.iter().map(|token| (token.kind(), token.as_string())).collect();
+        assert_eq!(names, [
+            (TokenKind::Symbol, "π".to_string()),
+            (TokenKind::Operator(Operator::from("+").unwrap()), "+".to_string()),
+            (TokenKind::Symbol, "été".to_string()),
+        ]);
+        assert_eq!((tokens[0].span().start(), tokens[0].span().end()), (0, "π".len()));
+    }
+
+    #[test]
+    fn escape_sequences() {
+        assert_eq!(first_value(r#""\x41\u{1F600}\n\"\\""#), Some(TokenValue::Text("A\u{1F600}\n\"\\".to_string())));
+
+        let rejected = [
+            (r#""\xFF""#, LexerErrorKind::MalformedEscape),
+            (r#""\u{D800}""#, LexerErrorKind::MalformedEscape),
+            (r#""\u{110000}""#, LexerErrorKind::MalformedEscape),
+            (r#""\u41""#, LexerErrorKind::MalformedEscape),
+            (r#""\q""#, LexerErrorKind::UnknownEscape),
+        ];
+        for (input, kind) in rejected {
+            let err = Lexer::new(input).parse().unwrap_err();
+            assert_eq!(err.kind, kind, "{input}");
+            assert_eq!(err.span.start(), 1, "{input}");
+        }
+    }
+
+    #[test]
+    fn character_literals_hold_one_character() {
+        assert_eq!(first_value(r"'\u{263A}'"), Some(TokenValue::Character('☺')));
+        assert_eq!(Lexer::new("'ab'").parse().unwrap_err().kind, LexerErrorKind::InvalidCharacterLiteral);
+        assert_eq!(Lexer::new("''").parse().unwrap_err().kind, LexerErrorKind::InvalidCharacterLiteral);
+        assert_eq!(Lexer::new("'a").parse().unwrap_err().kind, LexerErrorKind::UnterminatedCharacter);
+    }
+
+    #[test]
+    fn block_comments_nest() {
+        let tokens = Lexer::new("/* a /* b */ c */ 1").parse().unwrap();
+        assert_eq!(tokens.len(), 1);
+        assert_eq!(Lexer::new("/* a /* b */ 1").parse().unwrap_err().kind, LexerErrorKind::UnterminatedComment);
+    }
+
+    #[test]
+    fn doc_comments_document_the_next_token() {
+        let tokens = Lexer::new("/// one\n/** two */\nx //// not doc\n/***/ /**/ y").parse().unwrap();
+        assert_eq!(tokens[0].documentation().as_deref(), Some(" one\n two "));
+        assert_eq!(tokens[1].documentation(), None);
+    }
+
+    #[test]
+    fn dot_is_a_decimal_point_only_inside_numbers() {
+        assert_eq!(first_value("1.5"), Some(TokenValue::Number(Number::Float(1.5))));
+
+        let kinds: Vec<_> = Lexer::new("a.b").parse().unwrap().iter().map(Token::kind).collect();
+        assert_eq!(kinds, [TokenKind::Symbol, TokenKind::Operator(Operator::from(".").unwrap()), TokenKind::Symbol]);
+    }
+
+    #[test]
+    fn literal_too_large_for_i64_is_unsigned() {
+        assert_eq!(first_value("0xFFFFFFFFFFFFFFFF"), Some(TokenValue::Number(Number::Unsigned(u64::MAX))));
+        assert_eq!(first_value("0x7FFFFFFFFFFFFFFF"), Some(TokenValue::Number(Number::Integer(i64::MAX))));
+    }
+
+    #[test]
+    fn digit_separators_only_follow_digits() {
+        assert_eq!(first_value("0xFF_FF"), Some(TokenValue::Number(Number::Integer(0xFFFF))));
+        assert_eq!(first_value("1_000e1_0"), Some(TokenValue::Number(Number::Float(1e13))));
+        for input in ["0x_1", "0b_1", "0o_1", "1._5", "1e_5"] {
+            assert_eq!(Lexer::new(input).parse().unwrap_err().kind, LexerErrorKind::MisplacedDigitSeparator, "{input}");
+        }
+    }
+
+    #[test]
+    fn prefix_without_digits() {
+        for input in ["0x", "0b", "0o"] {
+            assert_eq!(Lexer::new(input).parse().unwrap_err().kind, LexerErrorKind::MissingDigits, "{input}");
+        }
+    }
+}
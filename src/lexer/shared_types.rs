@@ -1,17 +1,34 @@
+use std::borrow::Cow;
 use std::fmt::{Display, Formatter};
+use crate::error::{LexerError, LexerErrorKind};
+use crate::lexer::shared_types::keywords::KeywordKind;
 use crate::lexer::shared_types::operators::Operator;
+pub use crate::lexer::shared_types::numbers::Number;
+pub use crate::lexer::shared_types::span::Span;
 pub use crate::lexer::shared_types::token_kinds::TokenKind;
+pub use crate::lexer::shared_types::token_values::{StringPart, TokenValue};
+pub use crate::lexer::shared_types::trivia::{Trivia, TriviaKind};
 
-pub mod states;
+pub(crate) mod states;
 pub mod token_kinds;
+pub mod token_values;
 pub mod operators;
+pub mod keywords;
+pub mod numbers;
+pub mod span;
+pub mod trivia;
 
 
 #[derive(Debug, Clone, PartialEq)]
 pub struct Token {
     kind: TokenKind,
-    value: Option<f64>,
+    value: Option<TokenValue>,
     id: String,
+    span: Span,
+    diagnostic: Option<LexerError>,
+    source: Option<String>,
+    leading_trivia: Vec<Trivia>,
+    trailing_trivia: Vec<Trivia>,
 }
 
 impl Token {
@@ -19,17 +36,51 @@ impl Token {
         self.kind
     }
 
-    pub fn update_kind(&mut self, new_kind: TokenKind) {
+    pub(crate) fn update_kind(&mut self, new_kind: TokenKind) {
         self.kind = new_kind;
     }
 
-    pub fn value(&self) -> Option<f64> {
-        self.value
+    pub fn value(&self) -> Option<&TokenValue> {
+        self.value.as_ref()
     }
 
     pub fn as_string(&self) -> String {
         self.id.clone()
     }
+
+    pub fn span(&self) -> Span {
+        self.span
+    }
+
+    /// Why the lexer could not make sense of this token, only set on `TokenKind::Unknown` tokens.
+    pub fn diagnostic(&self) -> Option<&LexerError> {
+        self.diagnostic.as_ref()
+    }
+
+    /// Exact source text of the token, only kept by a lossless lexer.
+    pub fn source(&self) -> Option<&str> {
+        self.source.as_deref()
+    }
+
+    /// Doc comments written right before this token, plus whitespace and comments in lossless mode.
+    pub fn leading_trivia(&self) -> &[Trivia] {
+        &self.leading_trivia
+    }
+
+    /// Whitespace and comments following this token on the same line, only kept by a lossless lexer.
+    pub fn trailing_trivia(&self) -> &[Trivia] {
+        &self.trailing_trivia
+    }
+
+    /// Every doc comment preceding this token, one per line and without their delimiters.
+    pub fn documentation(&self) -> Option<String> {
+        let lines: Vec<&str> = self.leading_trivia.iter().filter_map(Trivia::documentation).collect();
+        if lines.is_empty() {
+            None
+        } else {
+            Some(lines.join("\n"))
+        }
+    }
 }
 
 // Private Methods
@@ -39,53 +90,148 @@ impl Token {
             kind: TokenKind::Unknown,
             value: None,
             id: "".to_string(),
+            span: Span::default(),
+            diagnostic: None,
+            source: None,
+            leading_trivia: vec![],
+            trailing_trivia: vec![],
         }
     }
-    fn from_digits(str: &String) -> Self {
-        Self {
-            kind: TokenKind::NumericLiteral,
-            value: Some(str.parse::<f64>().unwrap()),
-            id: str.clone(),
-        }
+
+    pub(crate) fn set_span(&mut self, span: Span) {
+        self.span = span;
     }
 
-    fn from_hex(str: &String) -> Self {
-        let hex_representation = str.trim_start_matches("0x");
-        let int_value = u64::from_str_radix(hex_representation, 16).unwrap_or(0);
-        let value = Some(int_value as f64);
+    fn set_source(&mut self, source: &str) {
+        self.source = Some(source.to_string());
+    }
 
-        Self {
+    fn set_leading_trivia(&mut self, trivia: Vec<Trivia>) {
+        self.leading_trivia = trivia;
+    }
+
+    fn set_trailing_trivia(&mut self, trivia: Vec<Trivia>) {
+        self.trailing_trivia = trivia;
+    }
+
+    fn from_digits(str: &str) -> Result<Self, LexerErrorKind> {
+        let digits = if str.contains('_') { Cow::Owned(str.replace('_', "")) } else { Cow::Borrowed(str) };
+        let number = if digits.contains(['.', 'e', 'E']) {
+            let float = digits.parse::<f64>().map_err(|_| LexerErrorKind::InvalidNumber)?;
+            if float.is_infinite() {
+                return Err(LexerErrorKind::NumericOverflow);
+            }
+            Number::Float(float)
+        } else {
+            Self::integer(&digits, 10)?
+        };
+
+        Ok(Self {
             kind: TokenKind::NumericLiteral,
-            value,
-            id: str.clone(),
+            value: Some(TokenValue::Number(number)),
+            id: str.to_string(),
+            span: Span::default(),
+            diagnostic: None,
+            source: None,
+            leading_trivia: vec![],
+            trailing_trivia: vec![],
+        })
+    }
+
+    fn from_hex(str: &str) -> Result<Self, LexerErrorKind> {
+        Self::from_radix(str, "0x", 16)
+    }
+
+    fn from_oct(str: &str) -> Result<Self, LexerErrorKind> {
+        Self::from_radix(str, "0o", 8)
+    }
+
+    fn from_bin(str: &str) -> Result<Self, LexerErrorKind> {
+        Self::from_radix(str, "0b", 2)
+    }
+
+    fn from_radix(str: &str, prefix: &str, radix: u32) -> Result<Self, LexerErrorKind> {
+        let digits = str.trim_start_matches(prefix).replace('_', "");
+        if digits.is_empty() {
+            return Err(LexerErrorKind::MissingDigits);
         }
+        let number = Self::integer(&digits, radix)?;
+
+        Ok(Self {
+            kind: TokenKind::NumericLiteral,
+            value: Some(TokenValue::Number(number)),
+            id: str.to_string(),
+            span: Span::default(),
+            diagnostic: None,
+            source: None,
+            leading_trivia: vec![],
+            trailing_trivia: vec![],
+        })
     }
 
-    fn from_bin(str: &String) -> Self {
-        let hex_representation = str.trim_start_matches("0b");
-        let int_value = u64::from_str_radix(hex_representation, 2).unwrap_or(0);
-        let value = Some(int_value as f64);
+    fn integer(digits: &str, radix: u32) -> Result<Number, LexerErrorKind> {
+        let unsigned = u64::from_str_radix(digits, radix).map_err(|_| LexerErrorKind::NumericOverflow)?;
+        Ok(Number::from_i128(unsigned as i128).unwrap())
+    }
 
+    pub(crate) fn from_str(raw: &str, decoded: &str) -> Self {
         Self {
-            kind: TokenKind::NumericLiteral,
-            value,
-            id: str.clone(),
+            kind: TokenKind::StringLiteral,
+            value: Some(TokenValue::Text(decoded.to_string())),
+            id: raw.to_string(),
+            span: Span::default(),
+            diagnostic: None,
+            source: None,
+            leading_trivia: vec![],
+            trailing_trivia: vec![],
         }
     }
 
-    fn from_str(str: &String) -> Self {
+    fn from_interpolation(raw: &str, parts: Vec<StringPart>) -> Self {
         Self {
             kind: TokenKind::StringLiteral,
-            value: None,
-            id: str.clone(),
+            value: Some(TokenValue::Interpolation(parts)),
+            id: raw.to_string(),
+            span: Span::default(),
+            diagnostic: None,
+            source: None,
+            leading_trivia: vec![],
+            trailing_trivia: vec![],
         }
     }
 
-    fn from_operator(op: Operator) -> Self {
+    fn from_char(raw: &str, decoded: char) -> Self {
+        Self {
+            kind: TokenKind::CharacterLiteral,
+            value: Some(TokenValue::Character(decoded)),
+            id: raw.to_string(),
+            span: Span::default(),
+            diagnostic: None,
+            source: None,
+            leading_trivia: vec![],
+            trailing_trivia: vec![],
+        }
+    }
+
+    /// `object.member` as a single symbol spanning both names.
+    pub(crate) fn qualified(object: &Token, member: &Token) -> Self {
+        let mut token = Self::new();
+        token.kind = TokenKind::Symbol;
+        token.id = format!("{}.{}", object.id, member.id);
+        token.span = Span::new(object.span.start(), member.span.end(), object.span.line(), object.span.column());
+        token
+    }
+
+    pub(crate) fn from_operator(op: Operator) -> Self {
         Self {
             kind: TokenKind::Operator(op),
             value: None,
             id: op.to_string(),
+            span: Span::default(),
+            diagnostic: None,
+            source: None,
+            leading_trivia: vec![],
+            trailing_trivia: vec![],
         }
     }
 
@@ -94,6 +240,11 @@ impl Token {
             kind: TokenKind::OpeningParenthesis,
             value: None,
             id: "(".to_string(),
+            span: Span::default(),
+            diagnostic: None,
+            source: None,
+            leading_trivia: vec![],
+            trailing_trivia: vec![],
         }
     }
 
@@ -102,6 +253,11 @@ impl Token {
             kind: TokenKind::ClosingParenthesis,
             value: None,
             id: ")".to_string(),
+            span: Span::default(),
+            diagnostic: None,
+            source: None,
+            leading_trivia: vec![],
+            trailing_trivia: vec![],
         }
     }
 
@@ -110,6 +266,11 @@ impl Token {
             kind: TokenKind::OpeningScope,
             value: None,
             id: "{".to_string(),
+            span: Span::default(),
+            diagnostic: None,
+            source: None,
+            leading_trivia: vec![],
+            trailing_trivia: vec![],
         }
     }
 
@@ -118,6 +279,11 @@ impl Token {
             kind: TokenKind::ClosingScope,
             value: None,
             id: "}".to_string(),
+            span: Span::default(),
+            diagnostic: None,
+            source: None,
+            leading_trivia: vec![],
+            trailing_trivia: vec![],
         }
     }
 
@@ -126,6 +292,24 @@ impl Token {
             kind: TokenKind::Separator,
             value: None,
             id: ",".to_string(),
+            span: Span::default(),
+            diagnostic: None,
+            source: None,
+            leading_trivia: vec![],
+            trailing_trivia: vec![],
+        }
+    }
+
+    fn end_of_input() -> Self {
+        Self {
+            kind: TokenKind::EndOfInput,
+            value: None,
+            id: "".to_string(),
+            span: Span::default(),
+            diagnostic: None,
+            source: None,
+            leading_trivia: vec![],
+            trailing_trivia: vec![],
         }
     }
 
@@ -134,20 +318,57 @@ impl Token {
             kind: TokenKind::EndOfStatement,
             value: None,
             id: ";".to_string(),
+            span: Span::default(),
+            diagnostic: None,
+            source: None,
+            leading_trivia: vec![],
+            trailing_trivia: vec![],
+        }
+    }
+
+    fn unknown(lexeme: &str, diagnostic: LexerError) -> Self {
+        Self {
+            kind: TokenKind::Unknown,
+            value: None,
+            id: lexeme.to_string(),
+            span: Span::default(),
+            diagnostic: Some(diagnostic),
+            source: None,
+            leading_trivia: vec![],
+            trailing_trivia: vec![],
         }
     }
 
-    fn symbol(name: &String) -> Self {
+    fn symbol_or_keyword(name: &str) -> Self {
+        let kind = match KeywordKind::from(name) {
+            Some(keyword) => TokenKind::Keyword(keyword),
+            None => TokenKind::Symbol,
+        };
+
         Self {
-            kind: TokenKind::Symbol,
+            kind,
             value: None,
-            id: name.clone(),
+            id: name.to_string(),
+            span: Span::default(),
+            diagnostic: None,
+            source: None,
+            leading_trivia: vec![],
+            trailing_trivia: vec![],
         }
     }
 }
 
 impl Display for Token {
     fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
-        write!(f, "{} : {} ({})", self.kind, self.id, self.value.unwrap_or(0.0))
+        write!(f, "{} : {} (", self.kind, self.id)?;
+        match &self.value {
+            Some(value) => write!(f, "{value}")?,
+            None => write!(f, "0")?,
+        }
+        write!(f, ") @ {}..{}", self.span.start(), self.span.end())?;
+        if let Some(diagnostic) = self.diagnostic() {
+            write!(f, " <- {diagnostic}")?;
+        }
+        Ok(())
     }
 }
@@ -0,0 +1,63 @@
+use std::fmt::{Display, Formatter};
+
+
+#[derive(Debug, Copy, Clone, Eq, PartialEq)]
+pub enum KeywordKind {
+    // Declarations
+    Let,
+    Fn,
+    // Control flow
+    If,
+    Else,
+    While,
+    For,
+    In,
+    Return,
+    Break,
+    Continue,
+    // Literals
+    True,
+    False,
+}
+
+impl KeywordKind {
+    pub fn from(str: &str) -> Option<Self> {
+        match str {
+            "let" => Some(KeywordKind::Let),
+            "fn" => Some(KeywordKind::Fn),
+            "if" => Some(KeywordKind::If),
+            "else" => Some(KeywordKind::Else),
+            "while" => Some(KeywordKind::While),
+            "for" => Some(KeywordKind::For),
+            "in" => Some(KeywordKind::In),
+            "return" => Some(KeywordKind::Return),
+            "break" => Some(KeywordKind::Break),
+            "continue" => Some(KeywordKind::Continue),
+            "true" => Some(KeywordKind::True),
+            "false" => Some(KeywordKind::False),
+            _ => None,
+        }
+    }
+}
+
+
+// Display Implementation
+impl Display for KeywordKind {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        let representation = match self {
+            KeywordKind::Let => "let",
+            KeywordKind::Fn => "fn",
+            KeywordKind::If => "if",
+            KeywordKind::Else => "else",
+            KeywordKind::While => "while",
+            KeywordKind::For => "for",
+            KeywordKind::In => "in",
+            KeywordKind::Return => "return",
+            KeywordKind::Break => "break",
+            KeywordKind::Continue => "continue",
+            KeywordKind::True => "true",
+            KeywordKind::False => "false",
+        };
+        write!(f, "{representation}")
+    }
+}
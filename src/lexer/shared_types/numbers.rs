@@ -0,0 +1,87 @@
+use std::cmp::Ordering;
+use std::fmt::{Display, Formatter};
+
+use crate::error::RuntimeErrorKind;
+
+
+/// Numeric value of a literal or of an intermediate result.
+///
+/// Integers stay exact as long as both operands are integral, floats only appear when the input has one or a division is inexact.
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub enum Number {
+    Integer(i64),
+    Unsigned(u64),
+    Float(f64),
+}
+
+impl Number {
+    /// Narrows an intermediate integer result, preferring the signed representation.
+    pub fn from_i128(value: i128) -> Option<Self> {
+        if let Ok(integer) = i64::try_from(value) {
+            Some(Number::Integer(integer))
+        } else if let Ok(unsigned) = u64::try_from(value) {
+            Some(Number::Unsigned(unsigned))
+        } else {
+            None
+        }
+    }
+
+    pub fn from_bool(value: bool) -> Self {
+        Number::Integer(value as i64)
+    }
+
+    pub fn as_f64(&self) -> f64 {
+        match self {
+            Number::Integer(integer) => *integer as f64,
+            Number::Unsigned(unsigned) => *unsigned as f64,
+            Number::Float(float) => *float,
+        }
+    }
+
+    /// `None` for floats, integral or not.
+    pub fn as_i128(&self) -> Option<i128> {
+        match self {
+            Number::Integer(integer) => Some(*integer as i128),
+            Number::Unsigned(unsigned) => Some(*unsigned as i128),
+            Number::Float(_) => None,
+        }
+    }
+
+    pub fn is_truthy(&self) -> bool {
+        match self {
+            Number::Integer(integer) => *integer != 0,
+            Number::Unsigned(unsigned) => *unsigned != 0,
+            Number::Float(float) => *float != 0.0,
+        }
+    }
+
+    /// Applies `integer` when both operands are integral and `float` as soon as one of them is not.
+    pub(crate) fn combine(self, other: Number, integer: fn(i128, i128) -> Option<i128>, float: fn(f64, f64) -> f64) -> Result<Number, RuntimeErrorKind> {
+        match (self.as_i128(), other.as_i128()) {
+            (Some(left), Some(right)) => integer(left, right)
+                .and_then(Number::from_i128)
+                .ok_or(RuntimeErrorKind::IntegerOverflow),
+            _ => Ok(Number::Float(float(self.as_f64(), other.as_f64()))),
+        }
+    }
+
+    pub fn compare(&self, other: &Number) -> Option<Ordering> {
+        match (self.as_i128(), other.as_i128()) {
+            (Some(left), Some(right)) => Some(left.cmp(&right)),
+            _ => self.as_f64().partial_cmp(&other.as_f64()),
+        }
+    }
+}
+
+
+// Display Implementation
+impl Display for Number {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Number::Integer(integer) => write!(f, "{integer}"),
+            Number::Unsigned(unsigned) => write!(f, "{unsigned}"),
+            // Debug keeps the decimal point on integral floats, so `2.0` doesn't read as an integer
+            Number::Float(float) => write!(f, "{float:?}"),
+        }
+    }
+}
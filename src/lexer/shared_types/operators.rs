@@ -1,7 +1,9 @@
+use std::cmp::Ordering;
 use std::fmt::{Display, Formatter};
 
-use anyhow::anyhow;
-
+use crate::error::RuntimeErrorKind;
+use crate::lexer::shared_types::keywords::KeywordKind;
+use crate::lexer::shared_types::numbers::Number;
 use crate::lexer::shared_types::Token;
 use crate::lexer::shared_types::token_kinds::TokenKind;
 
@@ -10,6 +12,17 @@ pub struct Operator {
     kind: OperatorKind,
     precedence: u8,
     arity: u8,
+    // `=` and compound assignments such as `+=`, whose kind is then the operator they apply
+    assignment: bool,
+}
+
+/// Which side a chain of operators of the same precedence groups from.
+#[derive(Debug, Copy, Clone, Eq, PartialEq)]
+pub enum Associativity {
+    /// `a - b - c` is `(a - b) - c`
+    Left,
+    /// `a ^ b ^ c` is `a ^ (b ^ c)`
+    Right,
 }
 
 #[derive(Debug, Copy, Clone, Eq, PartialEq)]
@@ -20,8 +33,21 @@ enum OperatorKind {
     Quotient,
     Difference,
     Sum,
+    Remainder,
     Negate,
     Positive,
+    // Bitwise
+    BitwiseAnd,
+    BitwiseOr,
+    BitwiseXor,
+    BitwiseNot,
+    ShiftLeft,
+    ShiftRight,
+    // Assignment
+    Assign,
+    // Conditional, `cond ? a : b`
+    Conditional,
+    ConditionalElse,
     // Logical
     LogicalOr,
     LogicalAnd,
@@ -32,6 +58,8 @@ enum OperatorKind {
     GreaterThanEqual,
     LessThan,
     LessThanEqual,
+    // Member access, `point.x`
+    MemberAccess,
 }
 
 impl Operator {
@@ -43,63 +71,141 @@ impl Operator {
         self.precedence
     }
 
-    pub fn compute_2(&self, left: f64, right: f64) -> f64 {
+    /// Exponentiation, prefix operators, conditionals and assignments group from the right, everything else from the left.
+    pub fn associativity(&self) -> Associativity {
+        if self.assignment {
+            return Associativity::Right;
+        }
+
+        match self.kind {
+            OperatorKind::Exp |
+            OperatorKind::Negate |
+            OperatorKind::Positive |
+            OperatorKind::LogicalNot |
+            OperatorKind::BitwiseNot |
+            OperatorKind::Conditional |
+            OperatorKind::ConditionalElse => Associativity::Right,
+            _ => Associativity::Left,
+        }
+    }
+
+    pub(crate) fn compute_2(&self, left: Number, right: Number) -> Result<Number, RuntimeErrorKind> {
         match self.kind {
-            OperatorKind::Exp => left.powf(right),
-            OperatorKind::Product => left * right,
-            OperatorKind::Quotient => left / right,
-            OperatorKind::Difference => left - right,
-            OperatorKind::Sum => left + right,
-            _ => 0.0
+            OperatorKind::Exp => match (left.as_i128(), right.as_i128()) {
+                (Some(base), Some(exponent)) if exponent >= 0 => u32::try_from(exponent).ok()
+                    .and_then(|exponent| base.checked_pow(exponent))
+                    .and_then(Number::from_i128)
+                    .ok_or(RuntimeErrorKind::IntegerOverflow),
+                _ => Ok(Number::Float(left.as_f64().powf(right.as_f64()))),
+            },
+            OperatorKind::Product => left.combine(right, i128::checked_mul, |l, r| l * r),
+            // Integers only stay integers when the division is exact, `7 / 2` is `3.5`
+            OperatorKind::Quotient => match (left.as_i128(), right.as_i128()) {
+                (Some(_), Some(0)) => Err(RuntimeErrorKind::DivisionByZero),
+                (Some(dividend), Some(divisor)) if dividend % divisor == 0 => Self::narrow(dividend / divisor),
+                _ => Ok(Number::Float(left.as_f64() / right.as_f64())),
+            },
+            OperatorKind::Difference => left.combine(right, i128::checked_sub, |l, r| l - r),
+            OperatorKind::Sum => left.combine(right, i128::checked_add, |l, r| l + r),
+            OperatorKind::Remainder => match Self::integers(left, right)? {
+                (_, 0) => Err(RuntimeErrorKind::DivisionByZero),
+                (left, right) => left.checked_rem(right).and_then(Number::from_i128).ok_or(RuntimeErrorKind::IntegerOverflow),
+            },
+            OperatorKind::BitwiseAnd => Self::integers(left, right).and_then(|(left, right)| Self::narrow(left & right)),
+            OperatorKind::BitwiseOr => Self::integers(left, right).and_then(|(left, right)| Self::narrow(left | right)),
+            OperatorKind::BitwiseXor => Self::integers(left, right).and_then(|(left, right)| Self::narrow(left ^ right)),
+            OperatorKind::ShiftLeft | OperatorKind::ShiftRight => {
+                let (value, amount) = Self::integers(left, right)?;
+                let amount = u32::try_from(amount).ok().filter(|amount| *amount < 64).ok_or(RuntimeErrorKind::InvalidShiftAmount)?;
+                if self.kind == OperatorKind::ShiftLeft {
+                    Self::narrow(value << amount)
+                } else {
+                    Self::narrow(value >> amount)
+                }
+            }
+            _ if self.is_comparison() => Ok(Number::from_bool(self.compare(left.compare(&right)))),
+            _ => Ok(Number::Integer(0)),
         }
     }
 
-    pub fn logical_compute_2(&self, left: bool, right: bool) -> bool {
+    /// Outcome of a comparison operator given how its operands are ordered, `None` meaning they cannot be ordered.
+    pub(crate) fn compare(&self, ordering: Option<Ordering>) -> bool {
+        match self.kind {
+            OperatorKind::Equals => ordering == Some(Ordering::Equal),
+            OperatorKind::Different => ordering != Some(Ordering::Equal),
+            OperatorKind::GreaterThan => ordering == Some(Ordering::Greater),
+            OperatorKind::GreaterThanEqual => matches!(ordering, Some(Ordering::Greater | Ordering::Equal)),
+            OperatorKind::LessThan => ordering == Some(Ordering::Less),
+            OperatorKind::LessThanEqual => matches!(ordering, Some(Ordering::Less | Ordering::Equal)),
+            _ => false,
+        }
+    }
+
+    pub(crate) fn logical_compute_2(&self, left: bool, right: bool) -> bool {
         match self.kind {
             OperatorKind::LogicalOr => left || right,
             OperatorKind::LogicalAnd => left && right,
-            OperatorKind::Equals => left == right,
-            OperatorKind::Different => left != right,
-            OperatorKind::GreaterThan => left > right,
-            OperatorKind::GreaterThanEqual => left >= right,
-            OperatorKind::LessThan => left < right,
-            OperatorKind::LessThanEqual => left <= right,
             _ => true,
         }
     }
 
-    pub fn compute_1(&self, operand: f64) -> f64 {
+    pub(crate) fn compute_1(&self, operand: Number) -> Result<Number, RuntimeErrorKind> {
         match self.kind {
-            OperatorKind::Negate => -operand,
-            OperatorKind::Positive => operand,
-            _ => 0.0,
+            OperatorKind::Negate => match operand.as_i128() {
+                Some(integer) => Number::from_i128(-integer).ok_or(RuntimeErrorKind::IntegerOverflow),
+                None => Ok(Number::Float(-operand.as_f64())),
+            },
+            OperatorKind::Positive => Ok(operand),
+            // Flips the bits of the value's own width, so unsigned values stay unsigned
+            OperatorKind::BitwiseNot => match operand {
+                Number::Integer(integer) => Ok(Number::Integer(!integer)),
+                Number::Unsigned(unsigned) => Ok(Number::Unsigned(!unsigned)),
+                Number::Float(_) => Err(RuntimeErrorKind::NonIntegerOperand),
+            },
+            _ => Ok(Number::Integer(0)),
         }
     }
 
-    pub fn logical_compute_1(&self, operand: bool) -> bool {
+    /// Operands of the operators that only make sense on integers, remainder and bitwise ones.
+    fn integers(left: Number, right: Number) -> Result<(i128, i128), RuntimeErrorKind> {
+        match (left.as_i128(), right.as_i128()) {
+            (Some(left), Some(right)) => Ok((left, right)),
+            _ => Err(RuntimeErrorKind::NonIntegerOperand),
+        }
+    }
+
+    fn narrow(value: i128) -> Result<Number, RuntimeErrorKind> {
+        Number::from_i128(value).ok_or(RuntimeErrorKind::IntegerOverflow)
+    }
+
+    pub(crate) fn logical_compute_1(&self, operand: bool) -> bool {
         match self.kind {
             OperatorKind::LogicalNot => !operand,
             _ => true,
         }
     }
 
-    pub fn correct_arity(self, previous: &Option<Token>) -> anyhow::Result<Self> {
+    pub(crate) fn correct_arity(self, previous: &Option<Token>) -> Self {
+        if self.assignment {
+            return self;
+        }
+
         let unary = if let Some(previous) = previous {
-            previous.kind != TokenKind::NumericLiteral && previous.kind != TokenKind::ClosingParenthesis
+            !matches!(previous.kind, TokenKind::NumericLiteral | TokenKind::StringLiteral | TokenKind::CharacterLiteral | TokenKind::Symbol | TokenKind::ClosingParenthesis | TokenKind::ClosingScope | TokenKind::Keyword(KeywordKind::True | KeywordKind::False))
         } else {
             true
         };
 
-        let out = match self.kind {
+        match self.kind {
             OperatorKind::Difference =>
                 if unary {
-                    Self::unary(OperatorKind::Negate, 5)
+                    Self::unary(OperatorKind::Negate, 14)
                 } else {
                     self
                 }
             OperatorKind::Sum =>
                 if unary {
-                    Self::unary(OperatorKind::Positive, 5)
+                    Self::unary(OperatorKind::Positive, 14)
                 } else {
                     self
                 },
@@ -107,54 +213,100 @@ impl Operator {
                 if unary {
                     self
                 } else {
-                    Self::binary(OperatorKind::Difference, 2)
+                    Self::binary(OperatorKind::Difference, 11)
                 }
             OperatorKind::Positive =>
                 if unary {
                     self
                 } else {
-                    Self::binary(OperatorKind::Sum, 2)
+                    Self::binary(OperatorKind::Sum, 11)
                 }
             _ => self,
-        };
+        }
+    }
 
-        Ok(out)
+    pub(crate) fn is_logical(&self) -> bool {
+        matches!(self.kind, OperatorKind::LogicalOr | OperatorKind::LogicalAnd | OperatorKind::LogicalNot)
     }
 
-    pub fn is_logical(&self) -> bool {
-        match self.kind {
-            OperatorKind::LogicalOr |
-            OperatorKind::LogicalAnd |
-            OperatorKind::LogicalNot |
-            OperatorKind::Equals |
-            OperatorKind::Different |
-            OperatorKind::GreaterThan |
-            OperatorKind::GreaterThanEqual |
-            OperatorKind::LessThan |
-            OperatorKind::LessThanEqual => true,
-            _ => false
+    pub(crate) fn is_assignment(&self) -> bool {
+        self.assignment
+    }
+
+    /// Operator a compound assignment applies before storing, `None` for a plain `=`.
+    pub(crate) fn compound(&self) -> Option<Operator> {
+        if self.assignment && self.kind != OperatorKind::Assign {
+            Some(Self::binary(self.kind, self.precedence))
+        } else {
+            None
         }
     }
+
+    /// The `.` between a name and one of its members, which the compiler folds into a qualified name.
+    pub(crate) fn is_member_access(&self) -> bool {
+        matches!(self.kind, OperatorKind::MemberAccess)
+    }
+
+    /// The `?` of a conditional, which the compiler turns into a jump over the first branch.
+    pub(crate) fn is_conditional(&self) -> bool {
+        matches!(self.kind, OperatorKind::Conditional)
+    }
+
+    /// The `:` of a conditional, which the compiler turns into a jump over the second branch.
+    pub(crate) fn is_conditional_else(&self) -> bool {
+        matches!(self.kind, OperatorKind::ConditionalElse)
+    }
+
+    pub(crate) fn is_concatenation(&self) -> bool {
+        matches!(self.kind, OperatorKind::Sum)
+    }
+
+    pub(crate) fn is_comparison(&self) -> bool {
+        matches!(self.kind, OperatorKind::Equals | OperatorKind::Different | OperatorKind::GreaterThan | OperatorKind::GreaterThanEqual | OperatorKind::LessThan | OperatorKind::LessThanEqual)
+    }
 }
 
 impl Operator {
-    pub fn from(str: &String) -> anyhow::Result<Self> {
-        match str.as_str() {
-            "+" => Ok(Self::binary(OperatorKind::Sum, 2)),
-            "-" => Ok(Self::binary(OperatorKind::Difference, 2)),
-            "*" => Ok(Self::binary(OperatorKind::Product, 3)),
-            "/" => Ok(Self::binary(OperatorKind::Quotient, 3)),
-            "^" | "**" => Ok(Self::binary(OperatorKind::Exp, 4)),
-            "!" => Ok(Self::unary(OperatorKind::LogicalNot, 4)),
-            "&&" => Ok(Self::binary(OperatorKind::LogicalAnd, 3)),
-            "||" => Ok(Self::binary(OperatorKind::LogicalOr, 3)),
-            "==" => Ok(Self::binary(OperatorKind::Equals, 1)),
-            "!=" => Ok(Self::binary(OperatorKind::Different, 1)),
-            ">" => Ok(Self::binary(OperatorKind::GreaterThan, 1)),
-            ">=" => Ok(Self::binary(OperatorKind::GreaterThanEqual, 1)),
-            "<" => Ok(Self::binary(OperatorKind::LessThan, 1)),
-            "<=" => Ok(Self::binary(OperatorKind::LessThanEqual, 1)),
-            str => Err(anyhow!("Unknown Operator {str}"))
+    // Precedences follow C, `^` being exponentiation and `^^` the exclusive or
+    pub fn from(str: &str) -> Option<Self> {
+        match str {
+            "=" => Some(Self::assignment(OperatorKind::Assign)),
+            "+=" => Some(Self::assignment(OperatorKind::Sum)),
+            "-=" => Some(Self::assignment(OperatorKind::Difference)),
+            "*=" => Some(Self::assignment(OperatorKind::Product)),
+            "/=" => Some(Self::assignment(OperatorKind::Quotient)),
+            "%=" => Some(Self::assignment(OperatorKind::Remainder)),
+            "^=" | "**=" => Some(Self::assignment(OperatorKind::Exp)),
+            "&=" => Some(Self::assignment(OperatorKind::BitwiseAnd)),
+            "|=" => Some(Self::assignment(OperatorKind::BitwiseOr)),
+            "^^=" => Some(Self::assignment(OperatorKind::BitwiseXor)),
+            "<<=" => Some(Self::assignment(OperatorKind::ShiftLeft)),
+            ">>=" => Some(Self::assignment(OperatorKind::ShiftRight)),
+            "?" => Some(Self::mixfix(OperatorKind::Conditional, 2)),
+            ":" => Some(Self::mixfix(OperatorKind::ConditionalElse, 2)),
+            "||" => Some(Self::binary(OperatorKind::LogicalOr, 3)),
+            "&&" => Some(Self::binary(OperatorKind::LogicalAnd, 4)),
+            "|" => Some(Self::binary(OperatorKind::BitwiseOr, 5)),
+            "^^" => Some(Self::binary(OperatorKind::BitwiseXor, 6)),
+            "&" => Some(Self::binary(OperatorKind::BitwiseAnd, 7)),
+            "==" => Some(Self::binary(OperatorKind::Equals, 8)),
+            "!=" => Some(Self::binary(OperatorKind::Different, 8)),
+            ">" => Some(Self::binary(OperatorKind::GreaterThan, 9)),
+            ">=" => Some(Self::binary(OperatorKind::GreaterThanEqual, 9)),
+            "<" => Some(Self::binary(OperatorKind::LessThan, 9)),
+            "<=" => Some(Self::binary(OperatorKind::LessThanEqual, 9)),
+            "<<" => Some(Self::binary(OperatorKind::ShiftLeft, 10)),
+            ">>" => Some(Self::binary(OperatorKind::ShiftRight, 10)),
+            "+" => Some(Self::binary(OperatorKind::Sum, 11)),
+            "-" => Some(Self::binary(OperatorKind::Difference, 11)),
+            "*" => Some(Self::binary(OperatorKind::Product, 12)),
+            "/" => Some(Self::binary(OperatorKind::Quotient, 12)),
+            "%" => Some(Self::binary(OperatorKind::Remainder, 12)),
+            "^" | "**" => Some(Self::binary(OperatorKind::Exp, 13)),
+            "!" => Some(Self::unary(OperatorKind::LogicalNot, 14)),
+            "~" => Some(Self::unary(OperatorKind::BitwiseNot, 14)),
+            "." => Some(Self::binary(OperatorKind::MemberAccess, 15)),
+            _ => None,
         }
     }
 
@@ -163,6 +315,7 @@ impl Operator {
             kind,
             precedence,
             arity: 1,
+            assignment: false,
         }
     }
 
@@ -171,6 +324,25 @@ impl Operator {
             kind,
             precedence,
             arity: 2,
+            assignment: false,
+        }
+    }
+
+    fn assignment(kind: OperatorKind) -> Self {
+        Self {
+            kind,
+            precedence: 1,
+            arity: 2,
+            assignment: true,
+        }
+    }
+
+    fn mixfix(kind: OperatorKind, precedence: u8) -> Self {
+        Self {
+            kind,
+            precedence,
+            arity: 3,
+            assignment: false,
         }
     }
 }
@@ -183,6 +355,16 @@ impl Display for OperatorKind {
             OperatorKind::Quotient => "/",
             OperatorKind::Difference | OperatorKind::Negate => "-",
             OperatorKind::Sum | OperatorKind::Positive => "+",
+            OperatorKind::Remainder => "%",
+            OperatorKind::BitwiseAnd => "&",
+            OperatorKind::BitwiseOr => "|",
+            OperatorKind::BitwiseXor => "^^",
+            OperatorKind::BitwiseNot => "~",
+            OperatorKind::ShiftLeft => "<<",
+            OperatorKind::ShiftRight => ">>",
+            OperatorKind::Assign => "",
+            OperatorKind::Conditional => "?",
+            OperatorKind::ConditionalElse => ":",
             OperatorKind::LogicalOr => "||",
             OperatorKind::LogicalAnd => "&&",
             OperatorKind::LogicalNot => "!",
@@ -192,6 +374,7 @@ impl Display for OperatorKind {
             OperatorKind::GreaterThanEqual => ">=",
             OperatorKind::LessThan => "<",
             OperatorKind::LessThanEqual => "<=",
+            OperatorKind::MemberAccess => ".",
         };
         write!(f, "{representation}")
     }
@@ -199,6 +382,47 @@ impl Display for OperatorKind {
 
 impl Display for Operator {
     fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
-        write!(f, "{}", self.kind.to_string())
+        if self.assignment {
+            write!(f, "{}=", self.kind)
+        } else {
+            write!(f, "{}", self.kind)
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn compute(operator: &str, left: Number, right: Number) -> Result<Number, RuntimeErrorKind> {
+        Operator::from(operator).unwrap().compute_2(left, right)
+    }
+
+    #[test]
+    fn remainder_follows_the_dividend_sign() {
+        assert_eq!(compute("%", Number::Integer(7), Number::Integer(3)), Ok(Number::Integer(1)));
+        assert_eq!(compute("%", Number::Integer(-7), Number::Integer(3)), Ok(Number::Integer(-1)));
+        assert_eq!(compute("%", Number::Integer(7), Number::Integer(0)), Err(RuntimeErrorKind::DivisionByZero));
+    }
+
+    #[test]
+    fn shifts() {
+        assert_eq!(compute("<<", Number::Integer(1), Number::Integer(10)), Ok(Number::Integer(1024)));
+        assert_eq!(compute(">>", Number::Integer(-8), Number::Integer(1)), Ok(Number::Integer(-4)));
+        assert_eq!(compute("<<", Number::Integer(1), Number::Integer(63)), Ok(Number::Unsigned(1 << 63)));
+        assert_eq!(compute("<<", Number::Integer(1), Number::Integer(64)), Err(RuntimeErrorKind::InvalidShiftAmount));
+        assert_eq!(compute(">>", Number::Integer(1), Number::Integer(-1)), Err(RuntimeErrorKind::InvalidShiftAmount));
+    }
+
+    #[test]
+    fn integer_only_operators_reject_floats() {
+        for operator in ["%", "&", "|", "^^", "<<", ">>"] {
+            assert_eq!(compute(operator, Number::Float(1.5), Number::Integer(1)), Err(RuntimeErrorKind::NonIntegerOperand), "{operator}");
+            assert_eq!(compute(operator, Number::Integer(1), Number::Float(1.0)), Err(RuntimeErrorKind::NonIntegerOperand), "{operator}");
+        }
+
+        let not = Operator::from("~").unwrap();
+        assert_eq!(not.compute_1(Number::Integer(0)), Ok(Number::Integer(-1)));
+        assert_eq!(not.compute_1(Number::Float(0.0)), Err(RuntimeErrorKind::NonIntegerOperand));
     }
 }
@@ -1,517 +1,1003 @@
 use std::iter::Peekable;
 use std::str::Chars;
 
-use anyhow::{anyhow, Result};
+use crate::error::{LexerError, LexerErrorKind};
 use crate::lexer::shared_types::operators::Operator;
-use crate::lexer::shared_types::Token;
+use crate::lexer::shared_types::{Span, StringPart, Token};
+use crate::lexer::shared_types::trivia::{Trivia, TriviaKind};
 use crate::lexer::TokenQueue;
 
-pub trait State {
-    fn handle<'a>(self: Box<Self>, temporary_data: TemporaryData<'a>) -> Result<(Box<dyn State>, TemporaryData<'a>)>;
-    fn is_final(&self) -> bool;
+type Result<T> = std::result::Result<T, LexerError>;
+
+/// Every state of the lexer, each character transition is a plain `match` on this enum.
+#[derive(Debug, Copy, Clone, Eq, PartialEq)]
+pub(crate) enum State {
+    Start,
+    End,
+    NewToken,
+    CompleteToken,
+    NumericLiteral,
+    ExponentStart,
+    ExponentDigits,
+    FancyNumericLiteral,
+    BinaryNumericLiteral,
+    OctalNumericLiteral,
+    HexNumericLiteral,
+    StringLiteral,
+    StringEscape,
+    RawStringLiteral,
+    CharacterLiteral,
+    CharacterEscape,
+    Operator,
+    ParenthesisOpen,
+    ParenthesisClose,
+    ScopeOpen,
+    ScopeClose,
+    Separator,
+    EndOfStatement,
+    SymbolName,
+    StartComment,
+    SingleLineComment,
+    MultiLineCommentBody,
+    MultiLineCommentEnd,
 }
 
-pub struct StartState;
-
-pub struct EndState;
-
-struct NewToken;
-
-struct CompleteToken;
-
-struct NumericLiteral;
-
-struct FancyNumericLiteral;
-
-struct BinaryNumericLiteral;
-
-struct HexNumericLiteral;
-
-struct StringLiteral;
-
-struct OperatorState;
-
-struct ParenthesisOpen;
-
-struct ParenthesisClose;
-
-struct ScopeOpen;
-
-struct ScopeClose;
-
-struct Separator;
-
-struct EndOfStatement;
-
-struct SymbolName;
-
-struct StartComment;
-struct SingleLineComment;
-struct MultiLineCommentBody;
-struct MultiLineCommentEnd;
-
 #[derive(Debug)]
-pub struct TemporaryData<'a> {
-    input: String,
+pub(crate) struct TemporaryData<'a> {
+    input: &'a str,
     output: TokenQueue,
     chars: Peekable<Chars<'a>>,
     current_token_string: String,
     current_token: Token,
 
     decimal_point_found: bool,
-    paren_balance_check: u8,
-    scope_balance_check: u8,
+    decoded_string: String,
+    string_parts: Vec<StringPart>,
+    raw_hashes: usize,
+    escape_start: Span,
+    paren_balance_check: Vec<Span>,
+    scope_balance_check: Vec<Span>,
+    interpolation: bool,
+    interpolation_closed: bool,
+    comment_depth: usize,
+    doc_comment: bool,
+    pending_trivia: Vec<Trivia>,
+    lossless: bool,
+    held_token: Option<Token>,
+
+    position: usize,
+    line: usize,
+    column: usize,
+    token_start: (usize, usize, usize),
 }
 
-impl State for StartState {
-    fn handle<'a>(self: Box<StartState>, temporary_data: TemporaryData<'a>) -> Result<(Box<dyn State>, TemporaryData<'a>)> {
-        if temporary_data.input.is_empty() {
-            Err(anyhow!("[PARSER] No input provided"))
-        } else {
-            Ok((Box::new(NewToken), temporary_data))
+impl State {
+    pub(crate) fn handle(self, temporary_data: &mut TemporaryData) -> Result<State> {
+        match self {
+            State::Start => start(temporary_data),
+            State::End => end(temporary_data),
+            State::NewToken => new_token(temporary_data),
+            State::CompleteToken => complete_token(temporary_data),
+            State::NumericLiteral => numeric_literal(temporary_data),
+            State::ExponentStart => exponent_start(temporary_data),
+            State::ExponentDigits => exponent_digits(temporary_data),
+            State::FancyNumericLiteral => fancy_numeric_literal(temporary_data),
+            State::BinaryNumericLiteral => binary_numeric_literal(temporary_data),
+            State::OctalNumericLiteral => octal_numeric_literal(temporary_data),
+            State::HexNumericLiteral => hex_numeric_literal(temporary_data),
+            State::StringLiteral => string_literal(temporary_data),
+            State::StringEscape => string_escape(temporary_data),
+            State::RawStringLiteral => raw_string_literal(temporary_data),
+            State::CharacterLiteral => character_literal(temporary_data),
+            State::CharacterEscape => character_escape(temporary_data),
+            State::Operator => operator(temporary_data),
+            State::ParenthesisOpen => parenthesis_open(temporary_data),
+            State::ParenthesisClose => parenthesis_close(temporary_data),
+            State::ScopeOpen => scope_open(temporary_data),
+            State::ScopeClose => scope_close(temporary_data),
+            State::Separator => separator(temporary_data),
+            State::EndOfStatement => end_of_statement(temporary_data),
+            State::SymbolName => symbol_name(temporary_data),
+            State::StartComment => start_comment(temporary_data),
+            State::SingleLineComment => single_line_comment(temporary_data),
+            State::MultiLineCommentBody => multi_line_comment_body(temporary_data),
+            State::MultiLineCommentEnd => multi_line_comment_end(temporary_data),
         }
     }
 
-    fn is_final(&self) -> bool {
-        false
+    pub(crate) fn is_final(&self) -> bool {
+        matches!(self, State::End)
+    }
+}
+
+fn start(temporary_data: &mut TemporaryData) -> Result<State> {
+    if temporary_data.input.is_empty() {
+        Err(temporary_data.token_error(LexerErrorKind::EmptyInput))
+    } else {
+        Ok(State::NewToken)
     }
 }
 
-impl State for NewToken {
-    fn handle<'a>(self: Box<NewToken>, mut temporary_data: TemporaryData<'a>) -> Result<(Box<dyn State>, TemporaryData<'a>)> {
-        temporary_data.current_token_string.clear();
-        temporary_data.current_token = Token::new();
-        temporary_data.decimal_point_found = false;
+fn new_token(temporary_data: &mut TemporaryData) -> Result<State> {
+    temporary_data.current_token_string.clear();
+    temporary_data.current_token = Token::new();
+    temporary_data.decimal_point_found = false;
+    temporary_data.decoded_string.clear();
+    temporary_data.string_parts.clear();
+    temporary_data.mark_token_start();
 
-        if let Some(&c) = temporary_data.chars.peek() {
-            if WHITESPACE[c as usize] {
-                temporary_data.chars.next();
-                Ok((Box::new(Self), temporary_data))
-            } else if '/' == c {
+    if let Some(&c) = temporary_data.chars.peek() {
+        if is_whitespace(c) {
+            whitespace(temporary_data, c);
+            Ok(State::NewToken)
+        } else if '/' == c {
+            temporary_data.current_token_string.push(c);
+            temporary_data.advance();
+            Ok(State::StartComment)
+        } else if is_in(&NUMERIC_DIGITS, c) {
+            Ok(if c == '0' {
                 temporary_data.current_token_string.push(c);
-                temporary_data.chars.next();
-                Ok((Box::new(StartComment), temporary_data))
-            } else if NUMERIC_DIGITS[c as usize] {
-                Ok((if c == '0' {
-                    temporary_data.current_token_string.push(c);
-                    temporary_data.chars.next();
-                    Box::new(FancyNumericLiteral)
-                } else {
-                    Box::new(NumericLiteral)
-                }, temporary_data))
-            } else if OPERATOR_CHARACTERS[c as usize] {
-                Ok((Box::new(OperatorState), temporary_data))
-            } else if '(' == c {
-                Ok((Box::new(ParenthesisOpen), temporary_data))
-            } else if ')' == c {
-                Ok((Box::new(ParenthesisClose), temporary_data))
-            } else if '{' == c {
-                Ok((Box::new(ScopeOpen), temporary_data))
-            } else if '}' == c {
-                Ok((Box::new(ScopeClose), temporary_data))
-            } else if ',' == c {
-                Ok((Box::new(Separator), temporary_data))
-            } else if ';' == c {
-                Ok((Box::new(EndOfStatement), temporary_data))
-            } else if '"' == c {
-                temporary_data.chars.next();
-                Ok((Box::new(StringLiteral), temporary_data))
+                temporary_data.advance();
+                State::FancyNumericLiteral
             } else {
-                Ok((Box::new(SymbolName), temporary_data))
+                State::NumericLiteral
+            })
+        } else if is_in(&OPERATOR_CHARACTERS, c) {
+            Ok(State::Operator)
+        } else if '(' == c {
+            Ok(State::ParenthesisOpen)
+        } else if ')' == c {
+            Ok(State::ParenthesisClose)
+        } else if '{' == c {
+            Ok(State::ScopeOpen)
+        } else if '}' == c {
+            Ok(State::ScopeClose)
+        } else if ',' == c {
+            Ok(State::Separator)
+        } else if ';' == c {
+            Ok(State::EndOfStatement)
+        } else if '"' == c {
+            temporary_data.advance();
+            Ok(State::StringLiteral)
+        } else if '\'' == c {
+            temporary_data.advance();
+            Ok(State::CharacterLiteral)
+        } else if let Some(hashes) = raw_string_opening(&temporary_data.input[temporary_data.position..]) {
+            // `r`, the hashes and the opening quote
+            for _ in 0..hashes + 2 {
+                temporary_data.advance();
             }
+            temporary_data.raw_hashes = hashes;
+            Ok(State::RawStringLiteral)
         } else {
-            Ok((Box::new(EndState), temporary_data))
+            Ok(State::SymbolName)
         }
-    }
-
-    fn is_final(&self) -> bool {
-        false
+    } else {
+        Ok(State::End)
     }
 }
 
-impl State for CompleteToken {
-    fn handle<'a>(self: Box<CompleteToken>, mut temporary_data: TemporaryData<'a>) -> Result<(Box<dyn State>, TemporaryData<'a>)> {
-        temporary_data.output.push_back(temporary_data.current_token.clone());
-        if let Some(_) = temporary_data.chars.peek() {
-            Ok((Box::new(NewToken), temporary_data))
-        } else {
-            Ok((Box::new(EndState), temporary_data))
+/// Consumes a run of whitespace, a line break always ending it so trivia can be split per line.
+fn whitespace(temporary_data: &mut TemporaryData, first: char) {
+    let mut c = first;
+    loop {
+        temporary_data.advance();
+        match temporary_data.chars.peek() {
+            Some(&next) if '\n' != c && is_whitespace(next) => c = next,
+            _ => break,
         }
     }
 
-    fn is_final(&self) -> bool {
-        false
+    if temporary_data.lossless {
+        temporary_data.push_trivia(TriviaKind::Whitespace);
     }
 }
 
-impl State for NumericLiteral {
-    fn handle<'a>(self: Box<NumericLiteral>, mut temporary_data: TemporaryData<'a>) -> Result<(Box<dyn State>, TemporaryData<'a>)> {
-        if let Some(&c) = temporary_data.chars.peek() {
-            if REAL_NUMERIC_DIGITS[c as usize] {
-                if '.' == c {
-                    if temporary_data.decimal_point_found {
-                        return Err(anyhow!("[PARSER] Multiple decimal separator found in the same numeric token"));
-                    } else {
-                        temporary_data.decimal_point_found = true;
-                    }
-                }
-                temporary_data.current_token_string.push(c);
-                temporary_data.chars.next();
+fn complete_token(temporary_data: &mut TemporaryData) -> Result<State> {
+    let span = temporary_data.token_span();
+    let mut token = std::mem::replace(&mut temporary_data.current_token, Token::new());
+    token.set_span(span);
+    temporary_data.emit(token);
+    temporary_data.mark_token_start();
+    if temporary_data.chars.peek().is_some() {
+        Ok(State::NewToken)
+    } else {
+        Ok(State::End)
+    }
+}
 
-                Ok((Box::new(Self), temporary_data))
-            } else {
-                if SYMBOL_CHARACTERS[c as usize] {
-                    Err(anyhow!("[PARSER] Invalid number/symbol"))
+fn numeric_literal(temporary_data: &mut TemporaryData) -> Result<State> {
+    if let Some(&c) = temporary_data.chars.peek() {
+        if is_in(&REAL_NUMERIC_DIGITS, c) {
+            if '.' == c {
+                if temporary_data.decimal_point_found {
+                    return Err(temporary_data.unexpected(LexerErrorKind::MultipleDecimalSeparators));
                 } else {
-                    temporary_data.current_token = Token::from_digits(&temporary_data.current_token_string);
-                    Ok((Box::new(CompleteToken), temporary_data))
+                    temporary_data.decimal_point_found = true;
                 }
             }
+            temporary_data.current_token_string.push(c);
+            temporary_data.advance();
+
+            Ok(State::NumericLiteral)
+        } else if '_' == c {
+            digit_separator_handler(temporary_data, &NUMERIC_DIGITS, 0, State::NumericLiteral)
+        } else if 'e' == c || 'E' == c {
+            if !temporary_data.current_token_string.ends_with(|last: char| last.is_ascii_digit()) {
+                return Err(temporary_data.unexpected(LexerErrorKind::MalformedExponent));
+            }
+            temporary_data.current_token_string.push(c);
+            temporary_data.advance();
+            Ok(State::ExponentStart)
+        } else if is_symbol_character(c) {
+            Err(temporary_data.unexpected(LexerErrorKind::InvalidNumber))
         } else {
-            temporary_data.current_token = Token::from_digits(&temporary_data.current_token_string);
-            Ok((Box::new(CompleteToken), temporary_data))
+            complete_numeric_literal(temporary_data, Token::from_digits)
         }
+    } else {
+        complete_numeric_literal(temporary_data, Token::from_digits)
     }
+}
 
-    fn is_final(&self) -> bool {
-        false
+fn exponent_start(temporary_data: &mut TemporaryData) -> Result<State> {
+    match temporary_data.chars.peek() {
+        Some(&c) if '+' == c || '-' == c => {
+            temporary_data.current_token_string.push(c);
+            temporary_data.advance();
+            Ok(State::ExponentDigits)
+        }
+        _ => Ok(State::ExponentDigits),
     }
 }
 
-impl State for StringLiteral {
-    fn handle<'a>(self: Box<StringLiteral>, mut temporary_data: TemporaryData<'a>) -> Result<(Box<dyn State>, TemporaryData<'a>)> {
-        if let Some(&c) = temporary_data.chars.peek() {
-            if '"' != c {
-                temporary_data.current_token_string.push(c);
-                temporary_data.chars.next();
-                Ok((Box::new(Self), temporary_data))
-            } else {
-                temporary_data.chars.next();
-                temporary_data.current_token = Token::from_str(&temporary_data.current_token_string);
-                Ok((Box::new(CompleteToken), temporary_data))
-            }
+fn exponent_digits(temporary_data: &mut TemporaryData) -> Result<State> {
+    let has_digits = temporary_data.current_token_string.ends_with(|last: char| last.is_ascii_digit() || '_' == last);
+
+    match temporary_data.chars.peek() {
+        Some(&c) if is_in(&NUMERIC_DIGITS, c) => {
+            temporary_data.current_token_string.push(c);
+            temporary_data.advance();
+            Ok(State::ExponentDigits)
+        }
+        Some(&'_') => digit_separator_handler(temporary_data, &NUMERIC_DIGITS, 0, State::ExponentDigits),
+        Some(&c) if is_symbol_character(c) || '.' == c => Err(temporary_data.unexpected(LexerErrorKind::MalformedExponent)),
+        _ if !has_digits => Err(temporary_data.token_error(LexerErrorKind::MalformedExponent)),
+        _ => complete_numeric_literal(temporary_data, Token::from_digits),
+    }
+}
+
+fn string_literal(temporary_data: &mut TemporaryData) -> Result<State> {
+    if let Some(&c) = temporary_data.chars.peek() {
+        if '\\' == c {
+            temporary_data.escape_start = temporary_data.location();
+            temporary_data.current_token_string.push(c);
+            temporary_data.advance();
+            Ok(State::StringEscape)
+        } else if '$' == c && temporary_data.input[temporary_data.position..].starts_with("${") {
+            string_interpolation(temporary_data)
+        } else if '"' != c {
+            temporary_data.current_token_string.push(c);
+            temporary_data.decoded_string.push(c);
+            temporary_data.advance();
+            Ok(State::StringLiteral)
         } else {
-            Err(anyhow!("[PARSER] Missing quotation mark \""))
+            temporary_data.advance();
+            temporary_data.current_token = if temporary_data.string_parts.is_empty() {
+                Token::from_str(&temporary_data.current_token_string, &temporary_data.decoded_string)
+            } else {
+                let text = std::mem::take(&mut temporary_data.decoded_string);
+                temporary_data.string_parts.push(StringPart::Text(text));
+                Token::from_interpolation(&temporary_data.current_token_string, std::mem::take(&mut temporary_data.string_parts))
+            };
+            Ok(State::CompleteToken)
         }
+    } else {
+        Err(temporary_data.token_error(LexerErrorKind::UnterminatedString))
     }
+}
+
+/// Lexes the expression of a `${...}` placeholder with a nested state machine, which stops at the matching `}`.
+///
+/// The text decoded so far becomes a part of its own, so the parts always alternate text and expression
+/// and start and end with a text part, empty or not.
+fn string_interpolation(temporary_data: &mut TemporaryData) -> Result<State> {
+    let opening = temporary_data.location();
+    let opening = Span::new(opening.start(), opening.start() + 2, opening.line(), opening.column());
+    temporary_data.advance();
+    temporary_data.advance();
+
+    let mut nested = TemporaryData::interpolation(temporary_data);
+    let result = nested.run();
+
+    // Carry on after whatever the nested lexer consumed, even when it failed
+    temporary_data.chars = nested.chars.clone();
+    temporary_data.position = nested.position;
+    temporary_data.line = nested.line;
+    temporary_data.column = nested.column;
+    result?;
+
+    if !nested.interpolation_closed {
+        return Err(LexerError::new(LexerErrorKind::UnterminatedInterpolation, "${", opening));
+    }
+    let placeholder = &temporary_data.input[opening.start()..temporary_data.position];
+    if nested.output.is_empty() {
+        let span = Span::new(opening.start(), temporary_data.position, opening.line(), opening.column());
+        return Err(LexerError::new(LexerErrorKind::EmptyInterpolation, placeholder, span));
+    }
+
+    temporary_data.current_token_string.push_str(placeholder);
+    let text = std::mem::take(&mut temporary_data.decoded_string);
+    temporary_data.string_parts.push(StringPart::Text(text));
+    temporary_data.string_parts.push(StringPart::Expression(nested.output));
+    Ok(State::StringLiteral)
+}
+
+/// Number of `#` in the `r#"` opening a raw string, `None` when `rest` does not start with one.
+fn raw_string_opening(rest: &str) -> Option<usize> {
+    let hashes = rest.strip_prefix('r')?;
+    let quote = hashes.trim_start_matches('#');
+    quote.starts_with('"').then_some(hashes.len() - quote.len())
+}
+
+/// Raw strings take everything verbatim, line breaks included, until a quote followed by as many `#` as the opening.
+fn raw_string_literal(temporary_data: &mut TemporaryData) -> Result<State> {
+    let Some(c) = temporary_data.advance() else {
+        return Err(temporary_data.token_error(LexerErrorKind::UnterminatedString));
+    };
 
-    fn is_final(&self) -> bool {
-        false
+    let rest = &temporary_data.input[temporary_data.position..];
+    if '"' == c && rest.bytes().take_while(|&b| b'#' == b).count() >= temporary_data.raw_hashes {
+        for _ in 0..temporary_data.raw_hashes {
+            temporary_data.advance();
+        }
+        temporary_data.current_token = Token::from_str(&temporary_data.current_token_string, &temporary_data.current_token_string);
+        Ok(State::CompleteToken)
+    } else {
+        temporary_data.current_token_string.push(c);
+        Ok(State::RawStringLiteral)
     }
 }
 
-impl State for SymbolName {
-    fn handle<'a>(self: Box<SymbolName>, mut temporary_data: TemporaryData<'a>) -> Result<(Box<dyn State>, TemporaryData<'a>)> {
-        if let Some(&c) = temporary_data.chars.peek() {
-            if SYMBOL_CHARACTERS[c as usize] {
-                temporary_data.current_token_string.push(c);
-                temporary_data.chars.next();
-                Ok((Box::new(Self), temporary_data))
-            } else {
-                // todo! Handle Keywords
-                temporary_data.current_token = Token::symbol(&temporary_data.current_token_string);
-                Ok((Box::new(CompleteToken), temporary_data))
+fn string_escape(temporary_data: &mut TemporaryData) -> Result<State> {
+    let decoded = escape_sequence(temporary_data, LexerErrorKind::UnterminatedString)?;
+    temporary_data.decoded_string.push(decoded);
+    Ok(State::StringLiteral)
+}
+
+fn character_literal(temporary_data: &mut TemporaryData) -> Result<State> {
+    match temporary_data.chars.peek() {
+        Some(&'\\') => {
+            temporary_data.escape_start = temporary_data.location();
+            temporary_data.current_token_string.push('\\');
+            temporary_data.advance();
+            Ok(State::CharacterEscape)
+        }
+        Some(&'\'') => {
+            temporary_data.advance();
+            let mut decoded = temporary_data.decoded_string.chars();
+            match (decoded.next(), decoded.next()) {
+                (Some(c), None) => {
+                    temporary_data.current_token = Token::from_char(&temporary_data.current_token_string, c);
+                    Ok(State::CompleteToken)
+                }
+                _ => Err(temporary_data.token_error(LexerErrorKind::InvalidCharacterLiteral)),
             }
-        } else {
-            temporary_data.current_token = Token::symbol(&temporary_data.current_token_string);
-            Ok((Box::new(CompleteToken), temporary_data))
         }
+        // A character literal never spans lines, so a stray quote cannot swallow the rest of the input
+        Some(&c) if '\n' != c => {
+            temporary_data.current_token_string.push(c);
+            temporary_data.decoded_string.push(c);
+            temporary_data.advance();
+            Ok(State::CharacterLiteral)
+        }
+        _ => Err(temporary_data.token_error(LexerErrorKind::UnterminatedCharacter)),
     }
+}
+
+fn character_escape(temporary_data: &mut TemporaryData) -> Result<State> {
+    let decoded = escape_sequence(temporary_data, LexerErrorKind::UnterminatedCharacter)?;
+    temporary_data.decoded_string.push(decoded);
+    Ok(State::CharacterLiteral)
+}
+
+/// Decodes the escape sequence following a backslash, shared by string and character literals.
+fn escape_sequence(temporary_data: &mut TemporaryData, unterminated: LexerErrorKind) -> Result<char> {
+    let Some(c) = temporary_data.advance() else {
+        return Err(temporary_data.token_error(unterminated));
+    };
+    temporary_data.current_token_string.push(c);
+
+    let decoded = match c {
+        '"' => '"',
+        '\'' => '\'',
+        '$' => '$',
+        '\\' => '\\',
+        'n' => '\n',
+        't' => '\t',
+        'r' => '\r',
+        '0' => '\0',
+        'x' => {
+            let code = temporary_data.take_hex_digits(2, 2);
+            match code {
+                Some(code) if code <= 0x7F => char::from(code as u8),
+                _ => return Err(temporary_data.escape_error(LexerErrorKind::MalformedEscape)),
+            }
+        }
+        'u' => {
+            if temporary_data.chars.peek() != Some(&'{') {
+                return Err(temporary_data.escape_error(LexerErrorKind::MalformedEscape));
+            }
+            temporary_data.current_token_string.push('{');
+            temporary_data.advance();
+
+            let code = temporary_data.take_hex_digits(1, 6);
+            if temporary_data.chars.peek() != Some(&'}') {
+                return Err(temporary_data.escape_error(LexerErrorKind::MalformedEscape));
+            }
+            temporary_data.current_token_string.push('}');
+            temporary_data.advance();
+
+            match code.and_then(char::from_u32) {
+                Some(decoded) => decoded,
+                None => return Err(temporary_data.escape_error(LexerErrorKind::MalformedEscape)),
+            }
+        }
+        _ => return Err(temporary_data.escape_error(LexerErrorKind::UnknownEscape)),
+    };
+
+    Ok(decoded)
+}
 
-    fn is_final(&self) -> bool {
-        false
+fn symbol_name(temporary_data: &mut TemporaryData) -> Result<State> {
+    if let Some(&c) = temporary_data.chars.peek() {
+        if temporary_data.current_token_string.is_empty() && !is_symbol_start(c) {
+            Err(temporary_data.unexpected(LexerErrorKind::UnexpectedCharacter))
+        } else if is_symbol_character(c) {
+            temporary_data.current_token_string.push(c);
+            temporary_data.advance();
+            Ok(State::SymbolName)
+        } else {
+            temporary_data.current_token = Token::symbol_or_keyword(&temporary_data.current_token_string);
+            Ok(State::CompleteToken)
+        }
+    } else {
+        temporary_data.current_token = Token::symbol_or_keyword(&temporary_data.current_token_string);
+        Ok(State::CompleteToken)
     }
 }
 
-impl State for OperatorState {
-    fn handle<'a>(self: Box<OperatorState>, mut temporary_data: TemporaryData<'a>) -> Result<(Box<dyn State>, TemporaryData<'a>)> {
-        if let Some(&c) = temporary_data.chars.peek() {
-            if OPERATOR_CHARACTERS[c as usize] {
-                let mut tmp_op = temporary_data.current_token_string.clone();
-                tmp_op.push(c);
-                if let Ok(_) = Operator::from(&tmp_op) {
-                    temporary_data.current_token_string.push(c);
-                    temporary_data.chars.next();
-                    Ok((Box::new(Self), temporary_data))
-                } else {
-                    if let Ok(op) = Operator::from(&temporary_data.current_token_string) {
-                        temporary_data.current_token = Token::from_operator(op);
-                        Ok((Box::new(CompleteToken), temporary_data))
-                    } else {
-                        temporary_data.current_token_string.push(c);
-                        temporary_data.chars.next();
-                        Ok((Box::new(Self), temporary_data))
-                    }
-                }
+fn operator(temporary_data: &mut TemporaryData) -> Result<State> {
+    if let Some(&c) = temporary_data.chars.peek() {
+        if is_in(&OPERATOR_CHARACTERS, c) {
+            // Tentatively extend the operator in place rather than allocating a candidate string
+            temporary_data.current_token_string.push(c);
+            let extended = Operator::from(&temporary_data.current_token_string).is_some();
+            temporary_data.current_token_string.pop();
+
+            if extended {
+                temporary_data.current_token_string.push(c);
+                temporary_data.advance();
+                Ok(State::Operator)
             } else {
-                if let Ok(op) = Operator::from(&temporary_data.current_token_string) {
+                if let Some(op) = Operator::from(&temporary_data.current_token_string) {
                     temporary_data.current_token = Token::from_operator(op);
-                    Ok((Box::new(CompleteToken), temporary_data))
+                    Ok(State::CompleteToken)
                 } else {
-                    Err(anyhow!("[PARSER] unrecognized operator: {}", temporary_data.current_token_string))
+                    temporary_data.current_token_string.push(c);
+                    temporary_data.advance();
+                    Ok(State::Operator)
                 }
             }
         } else {
-            Err(anyhow!("[PARSER] Operators should always be followed by another token"))
+            if let Some(op) = Operator::from(&temporary_data.current_token_string) {
+                temporary_data.current_token = Token::from_operator(op);
+                Ok(State::CompleteToken)
+            } else {
+                Err(temporary_data.token_error(LexerErrorKind::UnrecognizedOperator))
+            }
         }
+    } else {
+        Err(temporary_data.token_error(LexerErrorKind::DanglingOperator))
     }
+}
 
-    fn is_final(&self) -> bool {
-        false
+fn fancy_numeric_literal(temporary_data: &mut TemporaryData) -> Result<State> {
+    if let Some(&c) = temporary_data.chars.peek() {
+        if 'x' == c {
+            temporary_data.current_token_string.push(c);
+            temporary_data.advance();
+            Ok(State::HexNumericLiteral)
+        } else if 'b' == c {
+            temporary_data.current_token_string.push(c);
+            temporary_data.advance();
+            Ok(State::BinaryNumericLiteral)
+        } else if 'o' == c {
+            temporary_data.current_token_string.push(c);
+            temporary_data.advance();
+            Ok(State::OctalNumericLiteral)
+        } else if is_in(&REAL_NUMERIC_DIGITS, c) || '_' == c || 'e' == c || 'E' == c {
+            Ok(State::NumericLiteral)
+        } else if is_symbol_character(c) {
+            Err(temporary_data.unexpected(LexerErrorKind::BadNumericLiteral))
+        } else {
+            complete_numeric_literal(temporary_data, Token::from_digits)
+        }
+    } else {
+        complete_numeric_literal(temporary_data, Token::from_digits)
     }
 }
 
-impl State for FancyNumericLiteral {
-    fn handle<'a>(self: Box<FancyNumericLiteral>, mut temporary_data: TemporaryData<'a>) -> Result<(Box<dyn State>, TemporaryData<'a>)> {
-        if let Some(&c) = temporary_data.chars.peek() {
-            if 'x' == c {
-                temporary_data.current_token_string.push(c);
-                temporary_data.chars.next();
-                Ok((Box::new(HexNumericLiteral), temporary_data))
-            } else if 'b' == c {
-                temporary_data.current_token_string.push(c);
-                temporary_data.chars.next();
-                Ok((Box::new(BinaryNumericLiteral), temporary_data))
-            } else if REAL_NUMERIC_DIGITS[c as usize] {
-                Ok((Box::new(NumericLiteral), temporary_data))
-            } else {
-                Err(anyhow!("[PARSER] Bad numeric literal"))
-            }
-        } else {
-            temporary_data.current_token = Token::from_digits(&temporary_data.current_token_string);
-            Ok((Box::new(CompleteToken), temporary_data))
+type NumericTokenBuilder = fn(&str) -> std::result::Result<Token, LexerErrorKind>;
+
+fn complete_numeric_literal(temporary_data: &mut TemporaryData, token_builder: NumericTokenBuilder) -> Result<State> {
+    match token_builder(&temporary_data.current_token_string) {
+        Ok(token) => {
+            temporary_data.current_token = token;
+            Ok(State::CompleteToken)
         }
+        Err(kind) => Err(temporary_data.token_error(kind)),
     }
+}
 
-    fn is_final(&self) -> bool {
-        false
+/// `_` is only accepted right after a digit (or another separator), never straight after a prefix or a dot.
+///
+/// `prefix_length` is the length of the `0x`, `0b` or `0o` the literal starts with, if any.
+fn digit_separator_handler(temporary_data: &mut TemporaryData, digits: &[bool; 256], prefix_length: usize, state: State) -> Result<State> {
+    let follows_digit = temporary_data.current_token_string.len() > prefix_length
+        && temporary_data.current_token_string.ends_with(|last: char| is_in(digits, last) || '_' == last);
+
+    if follows_digit {
+        temporary_data.current_token_string.push('_');
+        temporary_data.advance();
+        Ok(state)
+    } else {
+        Err(temporary_data.unexpected(LexerErrorKind::MisplacedDigitSeparator))
     }
 }
 
-fn fancy_numeric_handler<'a, S: State + 'static>(mut temporary_data: TemporaryData<'a>, digits: [bool; 256], state: S, kind: &str, token_builder: fn(&String) -> Token) -> Result<(Box<dyn State>, TemporaryData<'a>)> {
+/// Digits of a `0x`, `0b` or `0o` literal, its two character prefix already in the token string.
+fn fancy_numeric_handler(temporary_data: &mut TemporaryData, digits: &[bool; 256], state: State, error_kind: LexerErrorKind, token_builder: NumericTokenBuilder) -> Result<State> {
     if let Some(&c) = temporary_data.chars.peek() {
-        if digits[c as usize] {
+        if is_in(digits, c) {
             temporary_data.current_token_string.push(c);
-            temporary_data.chars.next();
-            Ok((Box::new(state), temporary_data))
-        } else if SYMBOL_CHARACTERS[c as usize] || '.' == c {
-            Err(anyhow!("[PARSER] Invalid {kind} number"))
+            temporary_data.advance();
+            Ok(state)
+        } else if '_' == c {
+            digit_separator_handler(temporary_data, digits, 2, state)
+        } else if is_symbol_character(c) || '.' == c {
+            Err(temporary_data.unexpected(error_kind))
         } else {
-            temporary_data.current_token = token_builder(&temporary_data.current_token_string);
-            Ok((Box::new(CompleteToken), temporary_data))
+            complete_numeric_literal(temporary_data, token_builder)
         }
     } else {
-        temporary_data.current_token = token_builder(&temporary_data.current_token_string);
-        Ok((Box::new(CompleteToken), temporary_data))
+        complete_numeric_literal(temporary_data, token_builder)
     }
 }
 
-impl State for BinaryNumericLiteral {
-    fn handle<'a>(self: Box<BinaryNumericLiteral>, temporary_data: TemporaryData<'a>) -> Result<(Box<dyn State>, TemporaryData<'a>)> {
-        fancy_numeric_handler(temporary_data, BINARY_NUMERIC_DIGITS, Self, "binary", Token::from_bin)
-    }
-
-    fn is_final(&self) -> bool {
-        false
-    }
+fn binary_numeric_literal(temporary_data: &mut TemporaryData) -> Result<State> {
+    fancy_numeric_handler(temporary_data, &BINARY_NUMERIC_DIGITS, State::BinaryNumericLiteral, LexerErrorKind::InvalidBinaryNumber, Token::from_bin)
 }
 
-impl State for HexNumericLiteral {
-    fn handle<'a>(self: Box<HexNumericLiteral>, temporary_data: TemporaryData<'a>) -> Result<(Box<dyn State>, TemporaryData<'a>)> {
-        fancy_numeric_handler(temporary_data, HEX_NUMERIC_DIGITS, Self, "hexadecimal", Token::from_hex)
-    }
+fn octal_numeric_literal(temporary_data: &mut TemporaryData) -> Result<State> {
+    fancy_numeric_handler(temporary_data, &OCTAL_NUMERIC_DIGITS, State::OctalNumericLiteral, LexerErrorKind::InvalidOctalNumber, Token::from_oct)
+}
 
-    fn is_final(&self) -> bool {
-        false
-    }
+fn hex_numeric_literal(temporary_data: &mut TemporaryData) -> Result<State> {
+    fancy_numeric_handler(temporary_data, &HEX_NUMERIC_DIGITS, State::HexNumericLiteral, LexerErrorKind::InvalidHexadecimalNumber, Token::from_hex)
 }
 
-fn single_character_handler(mut temporary_data: TemporaryData, balancer: fn(temporary_data: &mut TemporaryData) -> (), token_builder: fn() -> Token) -> Result<(Box<dyn State>, TemporaryData)> {
-    balancer(&mut temporary_data);
-    temporary_data.chars.next();
+fn single_character_handler(temporary_data: &mut TemporaryData, balancer: fn(temporary_data: &mut TemporaryData) -> Result<()>, token_builder: fn() -> Token) -> Result<State> {
+    balancer(temporary_data)?;
+    temporary_data.advance();
     temporary_data.current_token = token_builder();
-    Ok((Box::new(CompleteToken), temporary_data))
+    Ok(State::CompleteToken)
 }
 
-impl State for ParenthesisOpen {
-    fn handle<'a>(self: Box<ParenthesisOpen>, temporary_data: TemporaryData<'a>) -> Result<(Box<dyn State>, TemporaryData<'a>)> {
-        single_character_handler(temporary_data, |temp: &mut TemporaryData| { temp.paren_balance_check += 1; }, Token::open_parenthesis)
-    }
+fn parenthesis_open(temporary_data: &mut TemporaryData) -> Result<State> {
+    single_character_handler(temporary_data, |temp: &mut TemporaryData| {
+        let span = temp.location();
+        temp.paren_balance_check.push(span);
+        Ok(())
+    }, Token::open_parenthesis)
+}
 
-    fn is_final(&self) -> bool {
-        false
-    }
+fn parenthesis_close(temporary_data: &mut TemporaryData) -> Result<State> {
+    single_character_handler(temporary_data, |temp: &mut TemporaryData| {
+        match temp.paren_balance_check.pop() {
+            Some(_) => Ok(()),
+            None => Err(temp.unexpected(LexerErrorKind::UnmatchedClosingParenthesis)),
+        }
+    }, Token::close_parenthesis)
 }
 
-impl State for ParenthesisClose {
+fn scope_open(temporary_data: &mut TemporaryData) -> Result<State> {
+    single_character_handler(temporary_data, |temp: &mut TemporaryData| {
+        let span = temp.location();
+        temp.scope_balance_check.push(span);
+        Ok(())
+    }, Token::open_scope)
+}
 
-    fn handle<'a>(self: Box<ParenthesisClose>, temporary_data: TemporaryData<'a>) -> Result<(Box<dyn State>, TemporaryData<'a>)> {
-        single_character_handler(temporary_data, |temp: &mut TemporaryData| { temp.paren_balance_check -= 1; }, Token::close_parenthesis)
+fn scope_close(temporary_data: &mut TemporaryData) -> Result<State> {
+    if temporary_data.interpolation && temporary_data.scope_balance_check.is_empty() {
+        // Closing brace of the `${...}` placeholder being lexed
+        temporary_data.advance();
+        temporary_data.interpolation_closed = true;
+        return Ok(State::End);
     }
 
-    fn is_final(&self) -> bool {
-        false
-    }
+    single_character_handler(temporary_data, |temp: &mut TemporaryData| {
+        match temp.scope_balance_check.pop() {
+            Some(_) => Ok(()),
+            None => Err(temp.unexpected(LexerErrorKind::UnmatchedClosingScope)),
+        }
+    }, Token::close_scope)
 }
 
-impl State for ScopeOpen {
+fn separator(temporary_data: &mut TemporaryData) -> Result<State> {
+    single_character_handler(temporary_data, |_: &mut TemporaryData| Ok(()), Token::separator)
+}
 
-    fn handle<'a>(self: Box<ScopeOpen>, temporary_data: TemporaryData<'a>) -> Result<(Box<dyn State>, TemporaryData<'a>)> {
-        single_character_handler(temporary_data, |temp: &mut TemporaryData| { temp.scope_balance_check += 1; }, Token::open_scope)
-    }
+fn end_of_statement(temporary_data: &mut TemporaryData) -> Result<State> {
+    single_character_handler(temporary_data, |_: &mut TemporaryData| Ok(()), Token::end_of_statement)
+}
 
-    fn is_final(&self) -> bool {
-        false
+fn end(temporary_data: &mut TemporaryData) -> Result<State> {
+    if let Some(span) = temporary_data.paren_balance_check.pop() {
+        Err(LexerError::new(LexerErrorKind::UnclosedParenthesis, "(", span))
+    } else if let Some(span) = temporary_data.scope_balance_check.pop() {
+        Err(LexerError::new(LexerErrorKind::UnclosedScope, "{", span))
+    } else {
+        if temporary_data.lossless {
+            // Whatever follows the last token needs a token to hang on to
+            temporary_data.mark_token_start();
+            let mut token = Token::end_of_input();
+            token.set_span(temporary_data.token_span());
+            temporary_data.emit(token);
+            if let Some(token) = temporary_data.held_token.take() {
+                temporary_data.output.push_back(token);
+            }
+        }
+        Ok(State::End)
     }
 }
 
-impl State for ScopeClose {
-    fn handle<'a>(self: Box<ScopeClose>, temporary_data: TemporaryData<'a>) -> Result<(Box<dyn State>, TemporaryData<'a>)> {
-            single_character_handler(temporary_data, |temp: &mut TemporaryData| { temp.scope_balance_check -= 1; }, Token::close_scope)
+// `///` and `/**` open doc comments, unlike `////`, `/***` and the empty `/**/`
+fn start_comment(temporary_data: &mut TemporaryData) -> Result<State> {
+    if let Some(&c) = temporary_data.chars.peek() {
+        let rest = &temporary_data.input[temporary_data.position..];
+        match c {
+            '/' => {
+                temporary_data.doc_comment = rest.starts_with("//") && !rest.starts_with("///");
+                temporary_data.advance();
+                Ok(State::SingleLineComment)
+            }
+            '*' => {
+                temporary_data.doc_comment = rest.starts_with("**") && !rest.starts_with("**/") && !rest.starts_with("***");
+                temporary_data.advance();
+                temporary_data.comment_depth = 1;
+                Ok(State::MultiLineCommentBody)
+            }
+            _ => Ok(State::Operator)
+        }
+    } else {
+        Err(temporary_data.token_error(LexerErrorKind::TrailingSlash))
     }
+}
 
-    fn is_final(&self) -> bool {
-        false
+fn single_line_comment(temporary_data: &mut TemporaryData) -> Result<State> {
+    match temporary_data.chars.peek() {
+        Some(&'\n') => {
+            temporary_data.complete_comment();
+            Ok(State::NewToken)
+        }
+        Some(_) => {
+            temporary_data.advance();
+            Ok(State::SingleLineComment)
+        }
+        None => {
+            temporary_data.complete_comment();
+            // Nothing past the comment belongs to a token, not even an error raised at the end of input
+            temporary_data.mark_token_start();
+            Ok(State::End)
+        }
     }
 }
 
-impl State for Separator {
-    fn handle<'a>(self: Box<Separator>, temporary_data: TemporaryData<'a>) -> Result<(Box<dyn State>, TemporaryData<'a>)> {
-        single_character_handler(temporary_data, |_: &mut TemporaryData| {}, Token::separator)
+fn multi_line_comment_body(temporary_data: &mut TemporaryData) -> Result<State> {
+    if let Some(c) = temporary_data.advance() {
+        if '*' == c {
+            Ok(State::MultiLineCommentEnd)
+        } else if '/' == c && temporary_data.chars.peek() == Some(&'*') {
+            // Block comments nest, so commenting out code that already holds one works
+            temporary_data.advance();
+            temporary_data.comment_depth += 1;
+            Ok(State::MultiLineCommentBody)
+        } else {
+            Ok(State::MultiLineCommentBody)
+        }
+    } else {
+        Err(temporary_data.token_error(LexerErrorKind::UnterminatedComment))
     }
+}
 
-    fn is_final(&self) -> bool {
-        false
+fn multi_line_comment_end(temporary_data: &mut TemporaryData) -> Result<State> {
+    if let Some(&c) = temporary_data.chars.peek() {
+        if '/' == c {
+            temporary_data.advance();
+            temporary_data.comment_depth -= 1;
+            if temporary_data.comment_depth == 0 {
+                temporary_data.complete_comment();
+                Ok(State::NewToken)
+            } else {
+                Ok(State::MultiLineCommentBody)
+            }
+        } else {
+            Ok(State::MultiLineCommentBody)
+        }
+    } else {
+        Err(temporary_data.token_error(LexerErrorKind::UnterminatedComment))
     }
 }
 
-impl State for EndOfStatement {
-    fn handle<'a>(self: Box<EndOfStatement>, temporary_data: TemporaryData<'a>) -> Result<(Box<dyn State>, TemporaryData<'a>)> {
-        single_character_handler(temporary_data, |_: &mut TemporaryData| {}, Token::end_of_statement)
+impl<'a> TemporaryData<'a> {
+    pub(crate) fn new(input: &'a str) -> TemporaryData<'a> {
+        Self {
+            input,
+            chars: input.chars().peekable(),
+            output: TokenQueue::new(),
+            current_token_string: String::new(),
+            current_token: Token::new(),
+            decimal_point_found: false,
+            decoded_string: String::new(),
+            string_parts: vec![],
+            raw_hashes: 0,
+            escape_start: Span::default(),
+            paren_balance_check: vec![],
+            scope_balance_check: vec![],
+            interpolation: false,
+            interpolation_closed: false,
+            comment_depth: 0,
+            doc_comment: false,
+            pending_trivia: vec![],
+            lossless: false,
+            held_token: None,
+            position: 0,
+            line: 1,
+            column: 1,
+            token_start: (0, 1, 1),
+        }
     }
 
-    fn is_final(&self) -> bool {
-        false
+    /// Lexer for the expression of a `${...}` placeholder, picking up where `parent` stands.
+    fn interpolation(parent: &TemporaryData<'a>) -> TemporaryData<'a> {
+        Self {
+            chars: parent.chars.clone(),
+            interpolation: true,
+            position: parent.position,
+            line: parent.line,
+            column: parent.column,
+            ..Self::new(parent.input)
+        }
     }
-}
 
-impl State for EndState {
-    fn handle<'a>(self: Box<EndState>, temporary_data: TemporaryData<'a>) -> Result<(Box<dyn State>, TemporaryData<'a>)> {
-        if temporary_data.paren_balance_check != 0 {
-            Err(anyhow!("[PARSER] Parentheses are not balanced"))
-        } else if temporary_data.scope_balance_check != 0 {
-            Err(anyhow!("[PARSER] Scope brackets are not balanced"))
-        } else {
-            Ok((Box::new(Self), temporary_data))
+    /// Drives the state machine to its end, used for nested lexers whose tokens are not streamed.
+    fn run(&mut self) -> Result<()> {
+        let mut state = State::NewToken;
+        loop {
+            let final_state = state.is_final();
+            let next = state.handle(self)?;
+            if final_state {
+                return Ok(());
+            }
+            state = next;
         }
     }
 
-    fn is_final(&self) -> bool {
-        true
+    /// Turns whatever the failing state was building into an `Unknown` token carrying `error`,
+    /// then skips to the next plausible token boundary and returns the state to resume from.
+    pub(crate) fn recover(&mut self, error: &LexerError, state: State) -> State {
+        if matches!(state, State::StringLiteral | State::StringEscape | State::CharacterEscape) {
+            // Drop the rest of the string or character literal, its closing quote included
+            let quote = self.input[self.token_start.0..].chars().next();
+            while let Some(c) = self.advance() {
+                if '\\' == c {
+                    self.advance();
+                } else if Some(c) == quote {
+                    break;
+                }
+            }
+        } else if error.span.end() > self.position {
+            // The offending character has only been peeked at, it belongs to the invalid lexeme
+            if let Some(c) = self.advance() {
+                if is_symbol_character(c) || '.' == c {
+                    while let Some(&c) = self.chars.peek() {
+                        if !is_symbol_character(c) && '.' != c {
+                            break;
+                        }
+                        self.advance();
+                    }
+                }
+            }
+        }
+
+        let span = self.token_span();
+        if span.end() > span.start() {
+            let lexeme = &self.input[span.start()..span.end()];
+            let mut token = Token::unknown(lexeme, error.clone());
+            token.set_span(span);
+            self.emit(token);
+        }
+
+        State::NewToken
     }
-}
 
-impl State for StartComment {
-    fn handle<'a>(self: Box<Self>, mut temporary_data: TemporaryData<'a>) -> Result<(Box<dyn State>, TemporaryData<'a>)> {
-        if let Some(c) = temporary_data.chars.peek() {
-            match c {
-                '/' => Ok((Box::new(SingleLineComment), temporary_data)),
-                '*' => Ok((Box::new(MultiLineCommentBody), temporary_data)),
-                &_ => Ok((Box::new(OperatorState), temporary_data))
-            }
+    fn advance(&mut self) -> Option<char> {
+        let c = self.chars.next()?;
+        self.position += c.len_utf8();
+        if '\n' == c {
+            self.line += 1;
+            self.column = 1;
         } else {
-            Err(anyhow!("[PARSER] Trailing slash"))
+            self.column += 1;
         }
+        Some(c)
     }
 
-    fn is_final(&self) -> bool {
-        false
+    /// Makes every token carry its source text and the whitespace and comments around it.
+    pub(crate) fn keep_trivia(&mut self) {
+        self.lossless = true;
     }
-}
 
-impl State for SingleLineComment {
-    fn handle<'a>(self: Box<Self>, mut temporary_data: TemporaryData<'a>) -> Result<(Box<dyn State>, TemporaryData<'a>)> {
-        if let Some(&c) = temporary_data.chars.peek() {
-            temporary_data.chars.next();
-            if '\n' == c {
-                Ok((Box::new(NewToken), temporary_data))
-            } else {
-                Ok((self, temporary_data))
-            }
-        } else {
-            Ok((Box::new(EndState), temporary_data))
+    /// Hands a finished token over to the output along with its trivia.
+    ///
+    /// In lossless mode a token is held back until the next one, as its trailing trivia are only known by then.
+    fn emit(&mut self, mut token: Token) {
+        if !self.lossless {
+            token.set_leading_trivia(std::mem::take(&mut self.pending_trivia));
+            self.output.push_back(token);
+            return;
         }
-    }
 
-    fn is_final(&self) -> bool {
-        false
+        let span = token.span();
+        token.set_source(&self.input[span.start()..span.end()]);
+        let mut leading = std::mem::take(&mut self.pending_trivia);
+        if let Some(mut previous) = self.held_token.take() {
+            let trailing_length = trailing_trivia_length(&leading);
+            let trailing = leading.drain(..trailing_length).collect();
+            previous.set_trailing_trivia(trailing);
+            self.output.push_back(previous);
+        }
+        token.set_leading_trivia(leading);
+        self.held_token = Some(token);
     }
-}
 
-impl State for MultiLineCommentBody {
-    fn handle<'a>(self: Box<Self>, mut temporary_data: TemporaryData<'a>) -> Result<(Box<dyn State>, TemporaryData<'a>)> {
-        if let Some(&c) = temporary_data.chars.peek() {
-            temporary_data.chars.next();
-            if '*' == c {
-                Ok((Box::new(MultiLineCommentEnd), temporary_data))
-            } else {
-                Ok((self, temporary_data))
-            }
-        } else {
-            Err(anyhow!("[PARSER] Unfinished multiline comment block"))
+    /// Keeps the comment that just ended, doc comments for the next token and the others only in lossless mode.
+    fn complete_comment(&mut self) {
+        if self.doc_comment {
+            self.push_trivia(TriviaKind::DocComment);
+        } else if self.lossless {
+            self.push_trivia(TriviaKind::Comment);
         }
     }
 
-    fn is_final(&self) -> bool {
-        false
+    /// Turns the text consumed since the token start into trivia.
+    fn push_trivia(&mut self, kind: TriviaKind) {
+        let span = self.token_span();
+        self.pending_trivia.push(Trivia::new(kind, &self.input[span.start()..span.end()], span));
     }
-}
 
-impl State for MultiLineCommentEnd {
-    fn handle<'a>(self: Box<Self>, mut temporary_data: TemporaryData<'a>) -> Result<(Box<dyn State>, TemporaryData<'a>)> {
-        if let Some(&c) = temporary_data.chars.peek() {
-            if '/' == c {
-                temporary_data.chars.next();
-                Ok((Box::new(NewToken), temporary_data))
-            } else {
-                Ok((Box::new(MultiLineCommentBody), temporary_data))
+    fn mark_token_start(&mut self) {
+        self.token_start = (self.position, self.line, self.column);
+    }
+
+    /// Span from the start of the current token up to the last consumed character.
+    fn token_span(&self) -> Span {
+        let (start, line, column) = self.token_start;
+        Span::new(start, self.position, line, column)
+    }
+
+    /// Error about the token being built, e.g. an operator that does not exist.
+    fn token_error(&self, kind: LexerErrorKind) -> LexerError {
+        LexerError::new(kind, &self.current_token_string, self.token_span())
+    }
+
+    /// Error about the next character, the one that cannot be accepted in the current state.
+    fn unexpected(&self, kind: LexerErrorKind) -> LexerError {
+        let token = self.chars.clone().next().map(String::from).unwrap_or_default();
+        LexerError::new(kind, &token, self.location())
+    }
+
+    /// Error about the escape sequence being decoded, from its backslash up to the last consumed character.
+    fn escape_error(&self, kind: LexerErrorKind) -> LexerError {
+        let span = Span::new(self.escape_start.start(), self.position, self.escape_start.line(), self.escape_start.column());
+        LexerError::new(kind, &self.input[span.start()..span.end()], span)
+    }
+
+    /// Consumes between `min` and `max` hexadecimal digits and returns their value.
+    fn take_hex_digits(&mut self, min: usize, max: usize) -> Option<u32> {
+        let mut digits = String::new();
+        while let Some(&c) = self.chars.peek() {
+            if digits.len() == max || !is_in(&HEX_NUMERIC_DIGITS, c) {
+                break;
             }
+            digits.push(c);
+            self.current_token_string.push(c);
+            self.advance();
+        }
+
+        if digits.len() < min {
+            None
         } else {
-            Err(anyhow!("[PARSER] Unfinished multiline comment block"))
+            u32::from_str_radix(&digits, 16).ok()
         }
     }
 
-    fn is_final(&self) -> bool {
-        false
+    /// Span of the next character, the one the current state is looking at.
+    fn location(&self) -> Span {
+        let end = self.position + self.chars.clone().next().map_or(0, char::len_utf8);
+        Span::new(self.position, end, self.line, self.column)
+    }
+
+    pub(crate) fn next_token(&mut self) -> Option<Token> {
+        self.output.pop_front()
     }
 }
 
-impl<'a> TemporaryData<'a> {
-    pub fn new(input: String, chars: Peekable<Chars<'a>>) -> TemporaryData<'a> {
-        Self {
-            input,
-            chars,
-            output: TokenQueue::new(),
-            current_token_string: String::new(),
-            current_token: Token::new(),
-            decimal_point_found: false,
-            paren_balance_check: 0,
-            scope_balance_check: 0,
+const NUMERIC_DIGITS: [bool; 256] = make_lut("0123456789");
+const REAL_NUMERIC_DIGITS: [bool; 256] = make_lut(".0123456789");
+const HEX_NUMERIC_DIGITS: [bool; 256] = make_lut("0123456789ABCDEFabcdef");
+const BINARY_NUMERIC_DIGITS: [bool; 256] = make_lut("01");
+const OCTAL_NUMERIC_DIGITS: [bool; 256] = make_lut("01234567");
+const WHITESPACE: [bool; 256] = make_lut(" \t\n\r\x0C");
+const OPERATOR_CHARACTERS: [bool; 256] = make_lut("!$%^&*+-=#@?:|`/\\<>~.");
+const SYMBOL_CHARACTERS: [bool; 256] = make_lut("abcdefghijklmnopqrstuvwxyzABCDEFGHIJKLMNOPQRSTUVWXYZ_0123456789");
+const SYMBOL_START_CHARACTERS: [bool; 256] = make_lut("abcdefghijklmnopqrstuvwxyzABCDEFGHIJKLMNOPQRSTUVWXYZ_");
+
+/// Trivia on the same line as the previous token belong to it, up to the line break included.
+/// Doc comments always go to the next token.
+fn trailing_trivia_length(trivia: &[Trivia]) -> usize {
+    let mut length = 0;
+    for item in trivia {
+        if item.kind() == TriviaKind::DocComment {
+            break;
         }
+        length += 1;
+        if item.text().ends_with('\n') {
+            break;
+        }
+    }
+    length
+}
+
+// ASCII goes through the lookup tables, anything else is classified with the Unicode rules.
+fn is_in(lookup_table: &[bool; 256], c: char) -> bool {
+    c.is_ascii() && lookup_table[c as usize]
+}
+
+fn is_whitespace(c: char) -> bool {
+    if c.is_ascii() {
+        WHITESPACE[c as usize]
+    } else {
+        c.is_whitespace()
     }
+}
 
-    pub fn output(self) -> TokenQueue {
-        self.output
+fn is_symbol_start(c: char) -> bool {
+    if c.is_ascii() {
+        SYMBOL_START_CHARACTERS[c as usize]
+    } else {
+        unicode_ident::is_xid_start(c)
     }
 }
 
-pub const NUMERIC_DIGITS: [bool; 256] = make_lut("0123456789");
-pub const REAL_NUMERIC_DIGITS: [bool; 256] = make_lut(".0123456789");
-pub const HEX_NUMERIC_DIGITS: [bool; 256] = make_lut("0123456789ABCDEFabcdef");
-pub const BINARY_NUMERIC_DIGITS: [bool; 256] = make_lut("01");
-pub const WHITESPACE: [bool; 256] = make_lut(" \t\n\r\x0C");
-pub const OPERATOR_CHARACTERS: [bool; 256] = make_lut("!$%^&*+-=#@?|`/\\<>~");
-pub const SYMBOL_CHARACTERS: [bool; 256] = make_lut("abcdefghijklmnopqrstuvwxyzABCDEFGHIJKLMNOPQRSTUVWXYZ_0123456789");
+fn is_symbol_character(c: char) -> bool {
+    if c.is_ascii() {
+        SYMBOL_CHARACTERS[c as usize]
+    } else {
+        unicode_ident::is_xid_continue(c)
+    }
+}
 
 const fn make_lut(s: &str) -> [bool; 256] {
     let mut lookup_table = [false; 256];
@@ -1,4 +1,5 @@
 use std::fmt::{Display, Formatter};
+use crate::lexer::shared_types::keywords::KeywordKind;
 use crate::lexer::shared_types::operators::Operator;
 
 
@@ -6,7 +7,10 @@ use crate::lexer::shared_types::operators::Operator;
 pub enum TokenKind {
     NumericLiteral,
     StringLiteral,
+    CharacterLiteral,
     Symbol,
+    /// Symbol followed by the parenthesized arguments it is called with, only produced by the compiler.
+    Function,
     Operator(Operator),
     Separator,
     OpeningParenthesis,
@@ -14,7 +18,9 @@ pub enum TokenKind {
     OpeningScope,
     ClosingScope,
     EndOfStatement,
-    Keyword,
+    Keyword(KeywordKind),
+    /// Only produced by a lossless lexer, to carry the trivia after the last token.
+    EndOfInput,
     Unknown,
 }
 
@@ -25,7 +31,9 @@ impl Display for TokenKind {
         let str = match self {
             TokenKind::NumericLiteral => "[LITERAL, NUMERIC  ]",
             TokenKind::StringLiteral => "[LITERAL, STRING   ]",
+            TokenKind::CharacterLiteral => "[LITERAL, CHARACTER]",
             TokenKind::Symbol => "[SYMBOL            ]",
+            TokenKind::Function => "[FUNCTION          ]",
             TokenKind::Operator { .. } => "[OPERATOR          ]",
             TokenKind::Separator => "[SEPARATOR         ]",
             TokenKind::OpeningParenthesis => "[PARENTHESIS, OPEN ]",
@@ -34,7 +42,8 @@ impl Display for TokenKind {
             TokenKind::OpeningScope => "[SCOPE, OPEN       ]",
             TokenKind::ClosingScope => "[SCOPE, CLOSE      ]",
             TokenKind::EndOfStatement => "[END OF STATEMENT  ]",
-            TokenKind::Keyword => "[KEYWORD           ]",
+            TokenKind::EndOfInput => "[END OF INPUT      ]",
+            TokenKind::Keyword { .. } => "[KEYWORD           ]",
         };
         write!(f, "{str}")
     }
@@ -0,0 +1,45 @@
+use std::fmt::{Display, Formatter};
+
+use crate::lexer::shared_types::numbers::Number;
+use crate::lexer::TokenQueue;
+
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum TokenValue {
+    Number(Number),
+    Text(String),
+    Character(char),
+    Interpolation(Vec<StringPart>),
+}
+
+/// Piece of an interpolated string, text and `${...}` expressions alternate starting and ending with text.
+#[derive(Debug, Clone, PartialEq)]
+pub enum StringPart {
+    Text(String),
+    Expression(TokenQueue),
+}
+
+
+// Display Implementation
+impl Display for TokenValue {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            TokenValue::Number(number) => write!(f, "{number}"),
+            TokenValue::Text(text) => write!(f, "{text:?}"),
+            TokenValue::Character(character) => write!(f, "{character:?}"),
+            TokenValue::Interpolation(parts) => {
+                write!(f, "\"")?;
+                for part in parts {
+                    match part {
+                        StringPart::Text(text) => write!(f, "{}", text.escape_debug())?,
+                        StringPart::Expression(tokens) => {
+                            let ids: Vec<String> = tokens.iter().map(|token| token.as_string()).collect();
+                            write!(f, "${{{}}}", ids.join(" "))?
+                        }
+                    }
+                }
+                write!(f, "\"")
+            }
+        }
+    }
+}
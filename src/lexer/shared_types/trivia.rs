@@ -0,0 +1,54 @@
+use crate::lexer::shared_types::span::Span;
+
+
+#[derive(Debug, Copy, Clone, Eq, PartialEq)]
+pub enum TriviaKind {
+    /// Run of whitespace, ending at the first line break.
+    Whitespace,
+    Comment,
+    /// `/// ...` line or `/** ... */` block documenting the token that follows it.
+    DocComment,
+}
+
+/// Source text that is not a token itself but belongs to one, kept alongside the token it precedes or follows.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Trivia {
+    kind: TriviaKind,
+    text: String,
+    span: Span,
+}
+
+impl Trivia {
+    pub fn kind(&self) -> TriviaKind {
+        self.kind
+    }
+
+    /// Exact source text, delimiters included.
+    pub fn text(&self) -> &str {
+        &self.text
+    }
+
+    pub fn span(&self) -> Span {
+        self.span
+    }
+
+    /// Text of a doc comment without its `///` or `/** */` delimiters.
+    pub fn documentation(&self) -> Option<&str> {
+        match self.kind {
+            TriviaKind::DocComment => self.text.strip_prefix("///")
+                .or_else(|| self.text.strip_prefix("/**").and_then(|text| text.strip_suffix("*/"))),
+            TriviaKind::Whitespace | TriviaKind::Comment => None,
+        }
+    }
+}
+
+// Private Methods
+impl Trivia {
+    pub(super) fn new(kind: TriviaKind, text: &str, span: Span) -> Self {
+        Self {
+            kind,
+            text: text.to_string(),
+            span,
+        }
+    }
+}
@@ -0,0 +1,35 @@
+//! Lexer, compiler and solver for the DIY language.
+//!
+//! Source text goes through three stages, each usable on its own:
+//! - [`Lexer`] turns the input into [`Token`]s, either all at once or one at a time as an iterator,
+//! - [`Compiler`] turns the tokens into an [`Expression`], a list of [`Instruction`]s in reverse polish notation,
+//!   by lowering the syntax tree [`Parser`] builds out of them,
+//! - [`Expression::solve`] evaluates it to a [`Value`].
+//!
+//! [`eval`] chains the three for the common case, [`eval_in`] keeps the variables it defines in an [`Environment`].
+
+pub mod compiler;
+pub mod error;
+pub mod lexer;
+pub mod parser;
+pub mod solver;
+
+pub use compiler::Compiler;
+pub use error::Error;
+pub use lexer::{Associativity, KeywordKind, Lexer, Number, Operator, Span, Token, TokenKind, TokenQueue, TokenValue};
+pub use parser::{Expr, Parser, Stmt};
+pub use solver::{Environment, Expression, Instruction, Value};
+
+/// Lexes, parses, compiles and solves `input`, stopping at the first error.
+pub fn eval(input: &str) -> Result<Value, Error> {
+    eval_in(input, &mut Environment::new())
+}
+
+/// Like [`eval`], but against `environment`, so variables defined by earlier inputs can be used.
+pub fn eval_in(input: &str, environment: &mut Environment) -> Result<Value, Error> {
+    let tokens = Lexer::new(input).parse()?;
+    let statements = Parser::new(&tokens).parse()?;
+    let expression = Compiler::new().lower(&statements)?;
+
+    Ok(expression.solve_in(environment)?)
+}
@@ -1,37 +1,38 @@
 use std::io::{BufRead, stdin};
 
-use anyhow::Result;
-
-use crate::lexer::{display_queue, Lexer};
-
-mod lexer;
-mod solver;
-mod compiler;
+use compiler::lexer::display_queue;
+use compiler::{Compiler, Environment, Error, Lexer, Parser};
 
 fn main() {
     let mut handle = stdin().lock();
     let mut input = String::new();
+    // Definitions carry over from one line to the next
+    let mut environment = Environment::new();
 
     loop {
         println!("Solve ('quit' or 'exit' to exit):");
-        handle.read_line(&mut input).expect("Failed to read line");
+        if handle.read_line(&mut input).expect("Failed to read line") == 0 {
+            println!("Exiting...");
+            break;
+        }
 
         while let Some('\n') | Some('\r') | Some(' ') = input.chars().next_back() {
             input.pop();
         }
 
-        input = input.replace("\\n", "\n");
-
         if input.eq_ignore_ascii_case("quit") || input.eq_ignore_ascii_case("exit") {
             println!("Exiting...");
             break;
         }
 
-        let result = process(&input);
+        let result = process(&input, &mut environment);
         match result {
             Ok(_) => {}
-            Err(err) => {
-                println!("{err}");
+            Err(errors) => {
+                for err in errors {
+                    println!("{err}");
+                    println!("{}", underline(&input, &err));
+                }
             }
         }
 
@@ -39,9 +40,27 @@ fn main() {
     }
 }
 
-fn process(expr: &String) -> Result<()> {
-    let token_queue = Lexer::new(expr.to_string()).parse()?;
+fn process(expr: &str, environment: &mut Environment) -> Result<(), Vec<Error>> {
+    let (token_queue, diagnostics) = Lexer::new(expr).parse_recovering();
     println!("{}", display_queue(&token_queue));
+    if !diagnostics.is_empty() {
+        return Err(diagnostics.into_iter().map(Error::from).collect());
+    }
+
+    let statements = Parser::new(&token_queue).parse().map_err(|err| vec![err.into()])?;
+    let expression = Compiler::new().lower(&statements).map_err(|err| vec![err.into()])?;
+    println!("= {}", expression.solve_in(environment).map_err(|err| vec![err.into()])?);
 
     Ok(())
 }
+
+/// Points at the offending part of the line the error was raised on.
+fn underline(input: &str, err: &Error) -> String {
+    let span = err.span();
+    let line_start = input[..span.start()].rfind('\n').map_or(0, |i| i + 1);
+    let line_end = input[span.start()..].find('\n').map_or(input.len(), |i| span.start() + i);
+    let padding = input[line_start..span.start()].chars().count();
+    let width = input[span.start()..span.end().min(line_end)].chars().count().max(1);
+
+    format!("{}\n{}{}", &input[line_start..line_end], " ".repeat(padding), "^".repeat(width))
+}
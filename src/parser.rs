@@ -0,0 +1,387 @@
+use crate::error::{CompilerError, CompilerErrorKind};
+use crate::lexer::{Associativity, KeywordKind, Operator, Span, StringPart, Token, TokenKind, TokenQueue, TokenValue};
+pub use ast::{Expr, Literal, Stmt};
+
+mod ast;
+
+/// Builds the syntax tree of a token queue by precedence climbing, binding powers being `Operator::precedence`.
+pub struct Parser<'a> {
+    tokens: &'a TokenQueue,
+    position: usize,
+    previous_token: Option<Token>,
+}
+
+impl<'a> Parser<'a> {
+    pub fn new(tokens: &'a TokenQueue) -> Self {
+        Self {
+            tokens,
+            position: 0,
+            previous_token: None,
+        }
+    }
+
+    /// Parses a whole program, statements separated by `;`.
+    pub fn parse(mut self) -> Result<Vec<Stmt>, CompilerError> {
+        let statements = self.statements(None)?;
+        match self.peek() {
+            Some(token) => Err(Self::unexpected(token)),
+            None => Ok(statements),
+        }
+    }
+
+    /// Parses `;` separated statements up to `terminator`, or the end of input, which is left for the caller.
+    ///
+    /// The last statement may be followed by a `;` as well, but there has to be at least one.
+    fn statements(&mut self, terminator: Option<TokenKind>) -> Result<Vec<Stmt>, CompilerError> {
+        let mut statements = vec![];
+        loop {
+            match self.peek() {
+                Some(token) if Some(token.kind()) == terminator && !statements.is_empty() => break,
+                None if !statements.is_empty() => break,
+                _ => statements.push(self.statement()?),
+            }
+
+            match self.peek() {
+                Some(token) if token.kind() == TokenKind::EndOfStatement => self.advance(),
+                Some(token) if Some(token.kind()) == terminator => break,
+                Some(token) => return Err(Self::unexpected(token)),
+                None => break,
+            }
+        }
+
+        Ok(statements)
+    }
+
+    fn statement(&mut self) -> Result<Stmt, CompilerError> {
+        match self.peek() {
+            Some(keyword) if keyword.kind() == TokenKind::Keyword(KeywordKind::Let) => {
+                let keyword = keyword.clone();
+                self.advance();
+                self.declaration(keyword)
+            }
+            _ => Ok(Stmt::Expression(self.expression(0)?)),
+        }
+    }
+
+    /// Parses `let name = value` after its keyword, the name possibly qualified as in `let point.x = 1`.
+    fn declaration(&mut self, keyword: Token) -> Result<Stmt, CompilerError> {
+        let invalid = |token: Option<&Token>| {
+            let token = token.unwrap_or(&keyword);
+            CompilerError::new(CompilerErrorKind::InvalidDeclaration, &token.as_string(), token.span())
+        };
+
+        let mut name = match self.peek() {
+            Some(name) if name.kind() == TokenKind::Symbol => name.clone(),
+            token => return Err(invalid(token)),
+        };
+        self.advance();
+        while let Some(TokenKind::Operator(operator)) = self.peek().map(Token::kind) {
+            if !operator.is_member_access() {
+                break;
+            }
+            self.advance();
+            match self.peek() {
+                Some(member) if member.kind() == TokenKind::Symbol => name = Token::qualified(&name, member),
+                token => return Err(invalid(token)),
+            }
+            self.advance();
+        }
+
+        match self.peek() {
+            Some(token) if matches!(token.kind(), TokenKind::Operator(operator) if operator.is_assignment() && operator.compound().is_none()) => {
+                self.advance();
+            }
+            token => return Err(invalid(token)),
+        }
+
+        let value = self.expression(0)?;
+        Ok(Stmt::Let { keyword, name, value: Box::new(value) })
+    }
+
+    /// Parses an expression spanning every remaining token.
+    fn complete_expression(&mut self) -> Result<Expr, CompilerError> {
+        let expression = self.expression(0)?;
+        match self.peek() {
+            Some(token) => Err(Self::unexpected(token)),
+            None => Ok(expression),
+        }
+    }
+
+    /// Parses operands and the operators joining them for as long as they bind at least as tightly as `min_precedence`.
+    fn expression(&mut self, min_precedence: u8) -> Result<Expr, CompilerError> {
+        let mut left = self.prefix()?;
+
+        while let Some(token) = self.peek() {
+            let operator = match token.kind() {
+                // Calls bind tighter than any operator
+                TokenKind::OpeningParenthesis => {
+                    let (Expr::Variable(callee), true) = (&left, self.follows_symbol()) else { break };
+                    left = self.call(callee.clone())?;
+                    continue;
+                }
+                TokenKind::Operator(operator) => operator.correct_arity(&self.previous_token),
+                _ => break,
+            };
+            // The `:` of a conditional is consumed by its `?`
+            if operator.arity() == 1 || operator.is_conditional_else() || operator.precedence() < min_precedence {
+                break;
+            }
+
+            let mut operator_token = token.clone();
+            operator_token.update_kind(TokenKind::Operator(operator));
+            left = self.infix(left, operator_token, operator)?;
+        }
+
+        Ok(left)
+    }
+
+    /// Parses what can start an expression: a literal, a variable, a parenthesized expression or a prefix operator.
+    fn prefix(&mut self) -> Result<Expr, CompilerError> {
+        let Some(token) = self.peek().cloned() else {
+            return Err(self.missing_operand());
+        };
+
+        match token.kind() {
+            TokenKind::NumericLiteral |
+            TokenKind::CharacterLiteral |
+            TokenKind::StringLiteral |
+            TokenKind::Keyword(KeywordKind::True | KeywordKind::False) => {
+                self.advance();
+                Self::literal(token)
+            }
+            TokenKind::Symbol => {
+                self.advance();
+                Ok(Expr::Variable(token))
+            }
+            TokenKind::OpeningParenthesis => {
+                self.advance();
+                let expression = self.expression(0)?;
+                self.expect_closing_parenthesis()?;
+                Ok(expression)
+            }
+            TokenKind::OpeningScope => {
+                self.advance();
+                let statements = self.statements(Some(TokenKind::ClosingScope))?;
+                match self.peek() {
+                    Some(closing) if closing.kind() == TokenKind::ClosingScope => self.advance(),
+                    Some(closing) => return Err(Self::unexpected(closing)),
+                    None => return Err(self.missing_operand()),
+                }
+                Ok(Expr::Block { statements, token })
+            }
+            TokenKind::Operator(operator) => {
+                let operator = operator.correct_arity(&self.previous_token);
+                if operator.arity() != 1 {
+                    return Err(CompilerError::new(CompilerErrorKind::ExpectedOperand, &token.as_string(), token.span()));
+                }
+
+                let mut operator_token = token;
+                operator_token.update_kind(TokenKind::Operator(operator));
+                self.advance();
+                let operand = self.expression(operator.precedence())?;
+                Ok(Expr::Unary { operator: operator_token, operand: Box::new(operand) })
+            }
+            TokenKind::ClosingParenthesis | TokenKind::ClosingScope | TokenKind::EndOfStatement | TokenKind::Separator => {
+                Err(CompilerError::new(CompilerErrorKind::ExpectedOperand, &token.as_string(), token.span()))
+            }
+            _ => Err(Self::unexpected(&token)),
+        }
+    }
+
+    /// Parses the right side of a binary, assignment, conditional or member access operator.
+    ///
+    /// The right side of a right associative operator binds at the operator's own precedence, so it takes in any
+    /// further operator of the same precedence. Both branches of a conditional are whole expressions, so
+    /// `c ? a : b = 1` assigns in the alternative.
+    fn infix(&mut self, left: Expr, token: Token, operator: Operator) -> Result<Expr, CompilerError> {
+        let follows_symbol = self.follows_symbol();
+        let right_precedence = match operator.associativity() {
+            Associativity::Left => operator.precedence() + 1,
+            Associativity::Right => operator.precedence(),
+        };
+        self.advance();
+
+        if operator.is_member_access() {
+            match (left, self.peek().cloned()) {
+                (Expr::Variable(object), Some(member)) if follows_symbol && member.kind() == TokenKind::Symbol => {
+                    self.advance();
+                    let qualified = Token::qualified(&object, &member);
+                    self.previous_token = Some(qualified.clone());
+                    Ok(Expr::Variable(qualified))
+                }
+                _ => Err(CompilerError::new(CompilerErrorKind::InvalidMemberAccess, &token.as_string(), token.span())),
+            }
+        } else if operator.is_assignment() {
+            let Expr::Variable(target) = left else {
+                return Err(CompilerError::new(CompilerErrorKind::InvalidAssignmentTarget, &token.as_string(), token.span()));
+            };
+            if !follows_symbol {
+                return Err(CompilerError::new(CompilerErrorKind::InvalidAssignmentTarget, &token.as_string(), token.span()));
+            }
+
+            let value = self.expression(right_precedence)?;
+            Ok(Expr::Assign { operator: token, target, value: Box::new(value) })
+        } else if operator.is_conditional() {
+            let then = self.expression(0)?;
+            match self.peek().map(Token::kind) {
+                Some(TokenKind::Operator(operator)) if operator.is_conditional_else() => self.advance(),
+                _ => return Err(CompilerError::new(CompilerErrorKind::MissingConditionalElse, &token.as_string(), token.span())),
+            }
+
+            let otherwise = self.expression(0)?;
+            Ok(Expr::Conditional { operator: token, condition: Box::new(left), then: Box::new(then), otherwise: Box::new(otherwise) })
+        } else {
+            let right = self.expression(right_precedence)?;
+            Ok(Expr::Binary { operator: token, left: Box::new(left), right: Box::new(right) })
+        }
+    }
+
+    /// Parses the comma separated arguments following a function name.
+    fn call(&mut self, callee: Token) -> Result<Expr, CompilerError> {
+        self.advance();
+
+        let mut arguments = vec![];
+        if !matches!(self.peek().map(Token::kind), Some(TokenKind::ClosingParenthesis)) {
+            loop {
+                arguments.push(self.expression(0)?);
+                match self.peek().map(Token::kind) {
+                    Some(TokenKind::Separator) => self.advance(),
+                    _ => break,
+                }
+            }
+        }
+        self.expect_closing_parenthesis()?;
+
+        Ok(Expr::Call { callee, arguments })
+    }
+
+    fn literal(token: Token) -> Result<Expr, CompilerError> {
+        let value = match (token.kind(), token.value()) {
+            (TokenKind::Keyword(KeywordKind::True), _) => Literal::Boolean(true),
+            (TokenKind::Keyword(KeywordKind::False), _) => Literal::Boolean(false),
+            (_, Some(TokenValue::Number(number))) => Literal::Number(*number),
+            (_, Some(TokenValue::Character(character))) => Literal::Character(*character),
+            (_, Some(TokenValue::Text(text))) => Literal::Text(text.clone()),
+            (_, Some(TokenValue::Interpolation(parts))) => return Self::interpolation(token.clone(), parts),
+            (_, None) => return Err(Self::unexpected(&token)),
+        };
+
+        Ok(Expr::Literal { value, token })
+    }
+
+    /// The synthetic text tokens point at the interpolated string, where any error will be reported.
+    fn interpolation(token: Token, parts: &[StringPart]) -> Result<Expr, CompilerError> {
+        let mut expressions = vec![];
+        for part in parts {
+            match part {
+                StringPart::Text(text) => {
+                    let mut text_token = Token::from_str(text, text);
+                    text_token.set_span(token.span());
+                    expressions.push(Expr::Literal { value: Literal::Text(text.clone()), token: text_token });
+                }
+                StringPart::Expression(tokens) => {
+                    expressions.push(Parser::new(tokens).complete_expression()?);
+                }
+            }
+        }
+
+        Ok(Expr::Interpolation { parts: expressions, token })
+    }
+
+    fn expect_closing_parenthesis(&mut self) -> Result<(), CompilerError> {
+        match self.peek() {
+            Some(token) if token.kind() == TokenKind::ClosingParenthesis => {
+                self.advance();
+                Ok(())
+            }
+            Some(token) => Err(Self::unexpected(token)),
+            None => Err(self.missing_operand()),
+        }
+    }
+
+    /// Next token to parse, the end of input marker of a lossless lexer counting as no token at all.
+    fn peek(&self) -> Option<&'a Token> {
+        self.tokens.get(self.position).filter(|token| token.kind() != TokenKind::EndOfInput)
+    }
+
+    fn advance(&mut self) {
+        self.previous_token = self.tokens.get(self.position).cloned();
+        self.position += 1;
+    }
+
+    /// Whether the last token was a name, the only thing that can be assigned to, called or accessed a member of.
+    fn follows_symbol(&self) -> bool {
+        matches!(&self.previous_token, Some(previous) if previous.kind() == TokenKind::Symbol)
+    }
+
+    fn missing_operand(&self) -> CompilerError {
+        match &self.previous_token {
+            Some(previous) => CompilerError::new(CompilerErrorKind::ExpectedOperand, &previous.as_string(), previous.span()),
+            None => CompilerError::new(CompilerErrorKind::ExpectedOperand, "", Span::default()),
+        }
+    }
+
+    /// Error for a token that cannot appear where it was found.
+    fn unexpected(token: &Token) -> CompilerError {
+        let kind = match token.kind() {
+            TokenKind::Operator(operator) if operator.is_conditional_else() => CompilerErrorKind::UnmatchedConditionalElse,
+            TokenKind::Keyword(_) => CompilerErrorKind::UnsupportedToken,
+            TokenKind::Unknown => CompilerErrorKind::UnknownToken,
+            _ => CompilerErrorKind::UnexpectedToken,
+        };
+
+        CompilerError::new(kind, &token.as_string(), token.span())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::lexer::Lexer;
+
+    fn parse(input: &str) -> Result<Vec<Stmt>, CompilerError> {
+        Parser::new(&Lexer::new(input).parse().unwrap()).parse()
+    }
+
+    fn tree(input: &str) -> String {
+        parse(input).unwrap().iter().map(Stmt::to_string).collect::<Vec<_>>().join("; ")
+    }
+
+    #[test]
+    fn associativity() {
+        assert_eq!(tree("1 - 2 - 3"), "(- (- 1 2) 3)");
+        assert_eq!(tree("2 ^ 3 ^ 2"), "(^ 2 (^ 3 2))");
+        assert_eq!(tree("2 ** 3 ** 2"), "(^ 2 (^ 3 2))");
+        assert_eq!(tree("- -2"), "(- (- 2))");
+        assert_eq!(tree("!!true"), "(! (! true))");
+        assert_eq!(tree("a = b += 1"), "(= a (+= b 1))");
+        assert_eq!(tree("a ? b : c ? d : e"), "(? a b (? c d e))");
+    }
+
+    #[test]
+    fn calls() {
+        assert_eq!(tree("max(1, 2 * 3, sqrt(4))"), "(max 1 (* 2 3) (sqrt 4))");
+        assert_eq!(tree("f()"), "(f)");
+        assert_eq!(tree("math.sqrt(2) + 1"), "(+ (math.sqrt 2) 1)");
+        for input in ["f(1,)", "f(, 1)", "f(1 2)", "(f)(1)"] {
+            assert!(parse(input).is_err(), "{input}");
+        }
+    }
+
+    #[test]
+    fn operators_need_their_operands() {
+        let rejected = [
+            ("* 0 0", CompilerErrorKind::ExpectedOperand),
+            ("2 ~ | true", CompilerErrorKind::UnexpectedToken),
+            ("1 a += << b", CompilerErrorKind::UnexpectedToken),
+            ("1 2", CompilerErrorKind::UnexpectedToken),
+            ("(1 +)", CompilerErrorKind::ExpectedOperand),
+            ("1 : 2", CompilerErrorKind::UnmatchedConditionalElse),
+            ("1 ? 2", CompilerErrorKind::MissingConditionalElse),
+            ("1 = 2", CompilerErrorKind::InvalidAssignmentTarget),
+        ];
+        for (input, kind) in rejected {
+            assert_eq!(parse(input).unwrap_err().kind, kind, "{input}");
+        }
+    }
+}
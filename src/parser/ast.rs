@@ -0,0 +1,96 @@
+use std::fmt::{Display, Formatter};
+
+use crate::lexer::{Number, Token};
+
+
+/// Value written as is in the source.
+#[derive(Debug, Clone, PartialEq)]
+pub enum Literal {
+    Number(Number),
+    Character(char),
+    Text(String),
+    Boolean(bool),
+}
+
+/// Node of the syntax tree, each one keeping the token its errors are reported at.
+#[derive(Debug, Clone, PartialEq)]
+#[allow(clippy::large_enum_variant)]
+pub enum Expr {
+    Literal { value: Literal, token: Token },
+    /// Interpolated string, its text and embedded expressions in source order.
+    Interpolation { parts: Vec<Expr>, token: Token },
+    /// Variable, its name qualified when it went through member access.
+    Variable(Token),
+    Unary { operator: Token, operand: Box<Expr> },
+    Binary { operator: Token, left: Box<Expr>, right: Box<Expr> },
+    /// `=` or a compound assignment, whose operator token still carries the operation to apply.
+    Assign { operator: Token, target: Token, value: Box<Expr> },
+    Conditional { operator: Token, condition: Box<Expr>, then: Box<Expr>, otherwise: Box<Expr> },
+    Call { callee: Token, arguments: Vec<Expr> },
+    /// `{ ... }` scope, its value being the one of its last statement.
+    Block { statements: Vec<Stmt>, token: Token },
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum Stmt {
+    Expression(Expr),
+    /// `let name = value`, defining the variable in the innermost scope.
+    Let { keyword: Token, name: Token, value: Box<Expr> },
+}
+
+
+// Display Implementation
+impl Display for Literal {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Literal::Number(number) => write!(f, "{number}"),
+            Literal::Character(character) => write!(f, "{character:?}"),
+            Literal::Text(text) => write!(f, "{text:?}"),
+            Literal::Boolean(boolean) => write!(f, "{boolean}"),
+        }
+    }
+}
+
+/// Prefix notation with every node parenthesized, `(+ 1 (* 2 3))`.
+impl Display for Expr {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Expr::Literal { value, .. } => write!(f, "{value}"),
+            Expr::Interpolation { parts, .. } => {
+                write!(f, "(concat")?;
+                for part in parts {
+                    write!(f, " {part}")?;
+                }
+                write!(f, ")")
+            }
+            Expr::Variable(name) => write!(f, "{}", name.as_string()),
+            Expr::Unary { operator, operand } => write!(f, "({} {operand})", operator.as_string()),
+            Expr::Binary { operator, left, right } => write!(f, "({} {left} {right})", operator.as_string()),
+            Expr::Assign { operator, target, value } => write!(f, "({} {} {value})", operator.as_string(), target.as_string()),
+            Expr::Conditional { condition, then, otherwise, .. } => write!(f, "(? {condition} {then} {otherwise})"),
+            Expr::Call { callee, arguments } => {
+                write!(f, "({}", callee.as_string())?;
+                for argument in arguments {
+                    write!(f, " {argument}")?;
+                }
+                write!(f, ")")
+            }
+            Expr::Block { statements, .. } => {
+                write!(f, "(block")?;
+                for statement in statements {
+                    write!(f, " {statement}")?;
+                }
+                write!(f, ")")
+            }
+        }
+    }
+}
+
+impl Display for Stmt {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Stmt::Expression(expression) => write!(f, "{expression}"),
+            Stmt::Let { name, value, .. } => write!(f, "(let {} {value})", name.as_string()),
+        }
+    }
+}
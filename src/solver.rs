@@ -1,53 +1,219 @@
 use std::fmt::{Display, Formatter};
 
-use anyhow::{anyhow, Result};
+use crate::error::{RuntimeError, RuntimeErrorKind};
+use crate::lexer::{KeywordKind, Number, Span, Token, TokenKind, TokenValue};
+pub use environment::Environment;
+pub use instruction::Instruction;
+pub use value::Value;
 
-use crate::lexer::{TokenKind, TokenQueue};
+pub(crate) mod builtins;
+mod environment;
+mod instruction;
+mod value;
 
 #[derive(Debug)]
 pub struct Expression {
-    rpn: TokenQueue,
+    instructions: Vec<Instruction>,
 }
 
 impl Expression {
-    pub fn new(rpn: TokenQueue) -> Self {
-        Self { rpn }
+    pub(crate) fn new(instructions: Vec<Instruction>) -> Self {
+        Self { instructions }
     }
 
-    pub fn solve(&self) -> Result<f64> {
+    pub fn instructions(&self) -> &[Instruction] {
+        &self.instructions
+    }
+
+    /// Solves the expression in an environment of its own, starting without any variable.
+    pub fn solve(&self) -> Result<Value, RuntimeError> {
+        self.solve_in(&mut Environment::new())
+    }
+
+    /// Solves the expression against `environment`, which keeps whatever it defines or assigns at its top level.
+    pub fn solve_in(&self, environment: &mut Environment) -> Result<Value, RuntimeError> {
+        let depth = environment.depth();
+        let result = self.run(environment);
+        environment.truncate(depth);
+        result
+    }
+
+    fn run(&self, environment: &mut Environment) -> Result<Value, RuntimeError> {
         let mut solve_stack = Vec::new();
+        let mut index = 0;
 
-        for token in self.rpn.iter() {
-            match token.kind() {
-                TokenKind::NumericLiteral => {
-                    solve_stack.push(token.value().unwrap());
+        while let Some(instruction) = self.instructions.get(index) {
+            index += 1;
+            match instruction {
+                Instruction::Token(token) => Self::apply(token, &mut solve_stack, environment)?,
+                Instruction::JumpUnless { target, token } => {
+                    let condition = match solve_stack.pop() {
+                        Some(Value::Number(number)) => number.is_truthy(),
+                        Some(_) => return Err(RuntimeError::new(RuntimeErrorKind::InvalidOperandType, &token.as_string(), token.span())),
+                        None => return Err(RuntimeError::new(RuntimeErrorKind::MissingOperand, &token.as_string(), token.span())),
+                    };
+                    if !condition {
+                        index = *target;
+                    }
+                }
+                Instruction::Jump { target } => index = *target,
+                Instruction::Call { function, arity } => Self::call(function, *arity, &mut solve_stack)?,
+                Instruction::Concat { count, token } => {
+                    let Some(start) = solve_stack.len().checked_sub(*count) else { return Err(RuntimeError::new(RuntimeErrorKind::MissingOperand, &token.as_string(), token.span())); };
+                    let text = solve_stack.drain(start..).map(|value| value.to_text()).collect();
+                    solve_stack.push(Value::Text(text));
+                }
+                Instruction::Store(token) => {
+                    let Some(value) = solve_stack.last() else { return Err(RuntimeError::new(RuntimeErrorKind::MissingOperand, &token.as_string(), token.span())); };
+                    if !environment.assign(&token.as_string(), value.clone()) {
+                        return Err(RuntimeError::new(RuntimeErrorKind::UndefinedVariable, &token.as_string(), token.span()));
+                    }
+                }
+                Instruction::Define(token) => {
+                    let Some(value) = solve_stack.last() else { return Err(RuntimeError::new(RuntimeErrorKind::MissingOperand, &token.as_string(), token.span())); };
+                    environment.define(&token.as_string(), value.clone());
+                }
+                Instruction::Pop => {
+                    solve_stack.pop();
                 }
-                TokenKind::Operator(operator) => {
-                    if operator.arity() == 2 {
-                        let Some(right) = solve_stack.pop() else { return Err(anyhow!("Malformed Expression")); };
-                        let Some(left) = solve_stack.pop() else { return Err(anyhow!("Malformed Expression")); };
+                Instruction::EnterScope => environment.enter_scope(),
+                Instruction::LeaveScope => environment.leave_scope(),
+            }
+        }
 
-                        solve_stack.push(operator.compute_2(left, right));
-                    } else if operator.arity() == 1 {
-                        let Some(operand) = solve_stack.pop() else { return Err(anyhow!("Malformed Expression")); };
+        match (solve_stack.pop(), solve_stack.is_empty()) {
+            (Some(result), true) => Ok(result),
+            (Some(_), false) => {
+                let last = self.instructions.iter().rev().find_map(Instruction::token).unwrap();
+                Err(RuntimeError::new(RuntimeErrorKind::LeftoverOperands, &last.as_string(), last.span()))
+            }
+            (None, _) => Err(RuntimeError::new(RuntimeErrorKind::EmptyExpression, "", Span::default())),
+        }
+    }
 
-                        solve_stack.push(operator.compute_1(operand));
-                    }
+    /// Pushes the value of a literal or variable, or replaces the operands of an operator with its result.
+    fn apply(token: &Token, solve_stack: &mut Vec<Value>, environment: &Environment) -> Result<(), RuntimeError> {
+        match token.kind() {
+            TokenKind::NumericLiteral => {
+                if let Some(TokenValue::Number(number)) = token.value() {
+                    solve_stack.push(Value::Number(*number));
+                }
+            }
+            TokenKind::StringLiteral => {
+                if let Some(TokenValue::Text(text)) = token.value() {
+                    solve_stack.push(Value::Text(text.clone()));
+                }
+            }
+            TokenKind::CharacterLiteral => {
+                if let Some(TokenValue::Character(character)) = token.value() {
+                    solve_stack.push(Value::Character(*character));
+                }
+            }
+            TokenKind::Symbol => {
+                // Variables shadow the built-in constants
+                let name = token.as_string();
+                let Some(value) = environment.get(&name).cloned().or_else(|| builtins::constant(&name).map(Value::Number)) else {
+                    return Err(RuntimeError::new(RuntimeErrorKind::UndefinedVariable, &name, token.span()));
+                };
+                solve_stack.push(value);
+            }
+            TokenKind::Keyword(KeywordKind::True) => {
+                solve_stack.push(Value::Number(Number::from_bool(true)));
+            }
+            TokenKind::Keyword(KeywordKind::False) => {
+                solve_stack.push(Value::Number(Number::from_bool(false)));
+            }
+            TokenKind::Operator(operator) => {
+                if operator.arity() == 2 {
+                    let Some(right) = solve_stack.pop() else { return Err(RuntimeError::new(RuntimeErrorKind::MissingOperand, &token.as_string(), token.span())); };
+                    let Some(left) = solve_stack.pop() else { return Err(RuntimeError::new(RuntimeErrorKind::MissingOperand, &token.as_string(), token.span())); };
+
+                    let result = Value::apply_2(&operator, left, right).map_err(|kind| RuntimeError::new(kind, &token.as_string(), token.span()))?;
+                    solve_stack.push(result);
+                } else if operator.arity() == 1 {
+                    let Some(operand) = solve_stack.pop() else { return Err(RuntimeError::new(RuntimeErrorKind::MissingOperand, &token.as_string(), token.span())); };
+
+                    let result = Value::apply_1(&operator, operand).map_err(|kind| RuntimeError::new(kind, &token.as_string(), token.span()))?;
+                    solve_stack.push(result);
                 }
-                _ => {}
             }
+            _ => {}
         }
 
-        Ok(solve_stack.pop().unwrap())
+        Ok(())
+    }
+}
+
+// Private Methods
+impl Expression {
+    /// Replaces the arguments of a call, the last `arity` values on the stack, with its result.
+    fn call(function: &Token, arity: usize, solve_stack: &mut Vec<Value>) -> Result<(), RuntimeError> {
+        let Some(first_argument) = solve_stack.len().checked_sub(arity) else {
+            return Err(RuntimeError::new(RuntimeErrorKind::MissingOperand, &function.as_string(), function.span()));
+        };
+        let Some(builtin) = builtins::function(&function.as_string()) else {
+            return Err(RuntimeError::new(RuntimeErrorKind::UnknownFunction, &function.as_string(), function.span()));
+        };
+
+        let arguments = solve_stack.split_off(first_argument).into_iter()
+            .map(|argument| match argument {
+                Value::Number(number) => Ok(number),
+                Value::Character(_) | Value::Text(_) => Err(RuntimeErrorKind::InvalidOperandType),
+            })
+            .collect::<Result<Vec<_>, _>>();
+        let result = arguments.and_then(|arguments| builtin.call(&arguments))
+            .map_err(|kind| RuntimeError::new(kind, &function.as_string(), function.span()))?;
+        solve_stack.push(Value::Number(result));
+        Ok(())
     }
 }
 
 impl Display for Expression {
     fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
         let mut str_representation = String::new();
-        for token in &self.rpn {
-            str_representation.push_str(format!("{token}\n").as_str());
+        for instruction in &self.instructions {
+            str_representation.push_str(format!("{instruction}\n").as_str());
         }
         write!(f, "{str_representation}")
     }
-}
\ No newline at end of file
+}
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{eval, eval_in, Error};
+
+    fn runtime_error(input: &str) -> RuntimeErrorKind {
+        match eval(input) {
+            Err(Error::Runtime(err)) => err.kind,
+            result => panic!("{input} gave {result:?}"),
+        }
+    }
+
+    #[test]
+    fn error_leaves_the_scopes_it_opened() {
+        let mut environment = Environment::new();
+        eval_in("let x = 1", &mut environment).unwrap();
+        assert!(eval_in("{ let y = 2; { 1/0 } }", &mut environment).is_err());
+
+        assert_eq!(environment.depth(), 1);
+        assert_eq!(environment.get("x"), Some(&Value::Number(Number::Integer(1))));
+        assert_eq!(environment.get("y"), None);
+    }
+
+    #[test]
+    fn interpolation_converts_to_text() {
+        assert_eq!(eval("\"${1}${'c'}${\"d\"}${1/2}\"").unwrap(), Value::Text("1cd0.5".to_string()));
+    }
+
+    #[test]
+    fn sum_only_concatenates_strings() {
+        assert_eq!(eval("\"a\" + \"b\"").unwrap(), Value::Text("ab".to_string()));
+        assert_eq!(runtime_error("\"1\" + 1"), RuntimeErrorKind::InvalidOperandType);
+        assert_eq!(runtime_error("'c' + \"d\""), RuntimeErrorKind::InvalidOperandType);
+    }
+
+    #[test]
+    fn assignment_needs_a_definition() {
+        assert_eq!(runtime_error("x = 1"), RuntimeErrorKind::UndefinedVariable);
+    }
+}
@@ -0,0 +1,144 @@
+use std::cmp::Ordering;
+use std::f64::consts::{E, PI, TAU};
+
+use crate::error::RuntimeErrorKind;
+use crate::lexer::Number;
+
+
+/// How many arguments a built-in function takes.
+#[derive(Debug, Copy, Clone, Eq, PartialEq)]
+pub(crate) enum Arity {
+    Exactly(usize),
+    AtLeast(usize),
+}
+
+impl Arity {
+    pub(crate) fn accepts(&self, count: usize) -> bool {
+        match self {
+            Arity::Exactly(arity) => count == *arity,
+            Arity::AtLeast(arity) => count >= *arity,
+        }
+    }
+}
+
+/// Function of the standard library, called with arguments whose count its arity already accepted.
+pub(crate) struct Builtin {
+    name: &'static str,
+    arity: Arity,
+    implementation: fn(&[Number]) -> Result<Number, RuntimeErrorKind>,
+}
+
+impl Builtin {
+    pub(crate) fn arity(&self) -> Arity {
+        self.arity
+    }
+
+    pub(crate) fn call(&self, arguments: &[Number]) -> Result<Number, RuntimeErrorKind> {
+        (self.implementation)(arguments)
+    }
+}
+
+/// Built-in function called `name`, which may be qualified with the `math` namespace.
+pub(crate) fn function(name: &str) -> Option<&'static Builtin> {
+    let name = unqualified(name);
+    FUNCTIONS.iter().find(|builtin| builtin.name == name)
+}
+
+/// Built-in constant called `name`, which may be qualified with the `math` namespace.
+pub(crate) fn constant(name: &str) -> Option<Number> {
+    let name = unqualified(name);
+    CONSTANTS.iter().find(|(constant, _)| *constant == name).map(|(_, value)| Number::Float(*value))
+}
+
+fn unqualified(name: &str) -> &str {
+    name.strip_prefix("math.").unwrap_or(name)
+}
+
+static CONSTANTS: [(&str, f64); 3] = [("pi", PI), ("e", E), ("tau", TAU)];
+
+// Integers stay integers through the functions that cannot make them fractional, floats follow IEEE 754
+static FUNCTIONS: [Builtin; 18] = [
+    Builtin { name: "sqrt", arity: Arity::Exactly(1), implementation: |arguments| float(arguments[0], f64::sqrt) },
+    Builtin { name: "abs", arity: Arity::Exactly(1), implementation: |arguments| integral(arguments[0], i128::abs, f64::abs) },
+    Builtin { name: "floor", arity: Arity::Exactly(1), implementation: |arguments| integral(arguments[0], |integer| integer, f64::floor) },
+    Builtin { name: "ceil", arity: Arity::Exactly(1), implementation: |arguments| integral(arguments[0], |integer| integer, f64::ceil) },
+    Builtin { name: "round", arity: Arity::Exactly(1), implementation: |arguments| integral(arguments[0], |integer| integer, f64::round) },
+    Builtin { name: "sin", arity: Arity::Exactly(1), implementation: |arguments| float(arguments[0], f64::sin) },
+    Builtin { name: "cos", arity: Arity::Exactly(1), implementation: |arguments| float(arguments[0], f64::cos) },
+    Builtin { name: "tan", arity: Arity::Exactly(1), implementation: |arguments| float(arguments[0], f64::tan) },
+    Builtin { name: "asin", arity: Arity::Exactly(1), implementation: |arguments| float(arguments[0], f64::asin) },
+    Builtin { name: "acos", arity: Arity::Exactly(1), implementation: |arguments| float(arguments[0], f64::acos) },
+    Builtin { name: "atan", arity: Arity::Exactly(1), implementation: |arguments| float(arguments[0], f64::atan) },
+    Builtin { name: "exp", arity: Arity::Exactly(1), implementation: |arguments| float(arguments[0], f64::exp) },
+    Builtin { name: "ln", arity: Arity::Exactly(1), implementation: |arguments| float(arguments[0], f64::ln) },
+    Builtin { name: "log", arity: Arity::Exactly(2), implementation: |arguments| Ok(Number::Float(arguments[0].as_f64().log(arguments[1].as_f64()))) },
+    Builtin { name: "min", arity: Arity::AtLeast(1), implementation: |arguments| Ok(extreme(arguments, Ordering::Less)) },
+    Builtin { name: "max", arity: Arity::AtLeast(1), implementation: |arguments| Ok(extreme(arguments, Ordering::Greater)) },
+    Builtin { name: "clamp", arity: Arity::Exactly(3), implementation: |arguments| Ok(clamp(arguments[0], arguments[1], arguments[2])) },
+    Builtin { name: "hypot", arity: Arity::Exactly(2), implementation: |arguments| Ok(Number::Float(arguments[0].as_f64().hypot(arguments[1].as_f64()))) },
+];
+
+fn float(argument: Number, function: fn(f64) -> f64) -> Result<Number, RuntimeErrorKind> {
+    Ok(Number::Float(function(argument.as_f64())))
+}
+
+fn integral(argument: Number, integer: fn(i128) -> i128, float: fn(f64) -> f64) -> Result<Number, RuntimeErrorKind> {
+    match argument.as_i128() {
+        Some(value) => Number::from_i128(integer(value)).ok_or(RuntimeErrorKind::IntegerOverflow),
+        None => Ok(Number::Float(float(argument.as_f64()))),
+    }
+}
+
+/// Smallest or greatest of the arguments, the first one winning ties and values that cannot be ordered.
+fn extreme(arguments: &[Number], wanted: Ordering) -> Number {
+    arguments.iter().skip(1).fold(arguments[0], |extreme, argument| {
+        if argument.compare(&extreme) == Some(wanted) { *argument } else { extreme }
+    })
+}
+
+/// Unlike `f64::clamp`, bounds in the wrong order do not panic, the lower one is checked first.
+fn clamp(value: Number, lower: Number, upper: Number) -> Number {
+    if value.compare(&lower) == Some(Ordering::Less) {
+        lower
+    } else if value.compare(&upper) == Some(Ordering::Greater) {
+        upper
+    } else {
+        value
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn call(name: &str, arguments: &[Number]) -> Number {
+        let builtin = function(name).unwrap();
+        assert!(builtin.arity().accepts(arguments.len()));
+        builtin.call(arguments).unwrap()
+    }
+
+    #[test]
+    fn integers_stay_integers_where_they_can() {
+        assert_eq!(call("abs", &[Number::Integer(-3)]), Number::Integer(3));
+        assert_eq!(call("floor", &[Number::Float(2.5)]), Number::Float(2.0));
+        assert_eq!(call("sqrt", &[Number::Integer(4)]), Number::Float(2.0));
+        assert_eq!(function("abs").unwrap().call(&[Number::Integer(i64::MIN)]), Ok(Number::Unsigned(1 << 63)));
+    }
+
+    #[test]
+    fn extremes_and_clamp() {
+        assert_eq!(call("min", &[Number::Integer(3), Number::Float(1.5), Number::Integer(2)]), Number::Float(1.5));
+        assert_eq!(call("max", &[Number::Integer(1), Number::Float(1.0)]), Number::Integer(1));
+        assert_eq!(call("clamp", &[Number::Integer(5), Number::Integer(0), Number::Integer(3)]), Number::Integer(3));
+        // Bounds in the wrong order do not panic
+        assert_eq!(call("clamp", &[Number::Integer(5), Number::Integer(3), Number::Integer(0)]), Number::Integer(0));
+    }
+
+    #[test]
+    fn math_namespace_is_optional() {
+        assert!(function("math.hypot").is_some());
+        assert_eq!(constant("math.pi"), constant("pi"));
+        assert_eq!(constant("tau"), Some(Number::Float(TAU)));
+        assert_eq!(constant("phi"), None);
+    }
+}
@@ -0,0 +1,66 @@
+use std::collections::HashMap;
+
+use crate::solver::Value;
+
+
+/// Variables an expression is solved against, kept between solves so later expressions see earlier definitions.
+///
+/// Each block opens a scope of its own on top of the global one, names resolving to the innermost scope defining them.
+#[derive(Debug, Clone)]
+pub struct Environment {
+    scopes: Vec<HashMap<String, Value>>,
+}
+
+impl Default for Environment {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Environment {
+    pub fn new() -> Self {
+        Self {
+            scopes: vec![HashMap::new()],
+        }
+    }
+
+    pub fn get(&self, name: &str) -> Option<&Value> {
+        self.scopes.iter().rev().find_map(|scope| scope.get(name))
+    }
+
+    /// Creates the variable in the innermost scope, shadowing any outer one or replacing one in that same scope.
+    pub fn define(&mut self, name: &str, value: Value) {
+        self.scopes.last_mut().unwrap().insert(name.to_string(), value);
+    }
+
+    /// Updates the innermost variable by that name, `false` when there is none.
+    pub(crate) fn assign(&mut self, name: &str, value: Value) -> bool {
+        match self.scopes.iter_mut().rev().find_map(|scope| scope.get_mut(name)) {
+            Some(variable) => {
+                *variable = value;
+                true
+            }
+            None => false,
+        }
+    }
+
+    pub(crate) fn enter_scope(&mut self) {
+        self.scopes.push(HashMap::new());
+    }
+
+    /// Drops the innermost scope and its variables, the global one always staying.
+    pub(crate) fn leave_scope(&mut self) {
+        if self.scopes.len() > 1 {
+            self.scopes.pop();
+        }
+    }
+
+    pub(crate) fn depth(&self) -> usize {
+        self.scopes.len()
+    }
+
+    /// Leaves every scope opened past `depth`, as after an error in the middle of a block.
+    pub(crate) fn truncate(&mut self, depth: usize) {
+        self.scopes.truncate(depth.max(1));
+    }
+}
@@ -0,0 +1,56 @@
+use std::fmt::{Display, Formatter};
+
+use crate::lexer::Token;
+
+
+/// One step of a compiled expression, run in order against the solver stack unless a jump says otherwise.
+#[derive(Debug, Clone, PartialEq)]
+pub enum Instruction {
+    /// Pushes a literal, or applies an operator to the values on top of the stack.
+    Token(Token),
+    /// Pops the condition of `token` and carries on at `target` when it is false.
+    JumpUnless { target: usize, token: Token },
+    Jump { target: usize },
+    /// Stores the value on top of the stack, leaving it there, in the existing variable named by the symbol.
+    Store(Token),
+    /// Like `Store`, but creates the variable in the innermost scope instead.
+    Define(Token),
+    /// Replaces the last `arity` values on the stack with the result of the function.
+    Call { function: Token, arity: usize },
+    /// Replaces the last `count` values on the stack with their text joined together, as the parts of `token`.
+    Concat { count: usize, token: Token },
+    /// Drops the value of a statement that is not the last of its block.
+    Pop,
+    EnterScope,
+    LeaveScope,
+}
+
+impl Instruction {
+    /// Source token of the instruction, where the errors it raises are reported.
+    pub fn token(&self) -> Option<&Token> {
+        match self {
+            Instruction::Token(token) | Instruction::JumpUnless { token, .. } | Instruction::Store(token) | Instruction::Define(token) | Instruction::Concat { token, .. } => Some(token),
+            Instruction::Call { function, .. } => Some(function),
+            Instruction::Jump { .. } | Instruction::Pop | Instruction::EnterScope | Instruction::LeaveScope => None,
+        }
+    }
+}
+
+
+// Display Implementation
+impl Display for Instruction {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Instruction::Token(token) => write!(f, "{token}"),
+            Instruction::JumpUnless { target, token } => write!(f, "[JUMP UNLESS       ] : {} -> {target}", token.as_string()),
+            Instruction::Jump { target } => write!(f, "[JUMP              ] : -> {target}"),
+            Instruction::Store(token) => write!(f, "[STORE             ] : {}", token.as_string()),
+            Instruction::Define(token) => write!(f, "[DEFINE            ] : {}", token.as_string()),
+            Instruction::Pop => write!(f, "[POP               ]"),
+            Instruction::EnterScope => write!(f, "[SCOPE, ENTER      ]"),
+            Instruction::LeaveScope => write!(f, "[SCOPE, LEAVE      ]"),
+            Instruction::Call { function, arity } => write!(f, "[CALL              ] : {}/{arity}", function.as_string()),
+            Instruction::Concat { count, .. } => write!(f, "[CONCAT            ] : {count}"),
+        }
+    }
+}
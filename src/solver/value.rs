@@ -0,0 +1,70 @@
+use std::fmt::{Display, Formatter};
+
+use crate::error::RuntimeErrorKind;
+use crate::lexer::{Number, Operator};
+
+
+/// Result of an expression, or any intermediate value on the solver stack.
+#[derive(Debug, Clone, PartialEq)]
+pub enum Value {
+    Number(Number),
+    Character(char),
+    Text(String),
+}
+
+impl Value {
+    /// Arithmetic and logic only apply to numbers, characters and strings can only be compared with their own kind.
+    ///
+    /// `+` concatenates two strings, anything else has to go through interpolation to become text.
+    pub(crate) fn apply_2(operator: &Operator, left: Value, right: Value) -> Result<Value, RuntimeErrorKind> {
+        match (left, right) {
+            (Value::Text(left), Value::Text(right)) if operator.is_concatenation() => Ok(Value::Text(left + &right)),
+            (Value::Text(left), Value::Text(right)) if operator.is_comparison() => {
+                Ok(Value::Number(Number::from_bool(operator.compare(Some(left.cmp(&right))))))
+            }
+            (Value::Number(left), Value::Number(right)) if operator.is_logical() => {
+                Ok(Value::Number(Number::from_bool(operator.logical_compute_2(left.is_truthy(), right.is_truthy()))))
+            }
+            (Value::Number(left), Value::Number(right)) => operator.compute_2(left, right).map(Value::Number),
+            (Value::Character(left), Value::Character(right)) if operator.is_comparison() => {
+                Ok(Value::Number(Number::from_bool(operator.compare(Some(left.cmp(&right))))))
+            }
+            _ => Err(RuntimeErrorKind::InvalidOperandType),
+        }
+    }
+
+    pub(crate) fn apply_1(operator: &Operator, operand: Value) -> Result<Value, RuntimeErrorKind> {
+        match operand {
+            Value::Number(operand) if operator.is_logical() => Ok(Value::Number(Number::from_bool(operator.logical_compute_1(operand.is_truthy())))),
+            Value::Number(operand) => operator.compute_1(operand).map(Value::Number),
+            Value::Character(_) | Value::Text(_) => Err(RuntimeErrorKind::InvalidOperandType),
+        }
+    }
+
+    /// Plain text of the value, unlike `Display` which quotes characters and strings.
+    pub(crate) fn to_text(&self) -> String {
+        match self {
+            Value::Text(text) => text.clone(),
+            Value::Character(character) => character.to_string(),
+            Value::Number(number) => number.to_string(),
+        }
+    }
+}
+
+impl From<Number> for Value {
+    fn from(number: Number) -> Self {
+        Value::Number(number)
+    }
+}
+
+
+// Display Implementation
+impl Display for Value {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Value::Number(number) => write!(f, "{number}"),
+            Value::Character(character) => write!(f, "{character:?}"),
+            Value::Text(text) => write!(f, "{text:?}"),
+        }
+    }
+}